@@ -0,0 +1,357 @@
+//! `#[derive(MoveStruct)]`, re-exported by `common`.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, parse_macro_input, parse_quote, Data, DeriveInput, Error, Fields, GenericParam,
+    Ident, LitStr, Path, Type,
+};
+
+/// Derive `MoveStruct`, `MoveType` and BCS-compatible serde impls for the Rust mirror of a Move
+/// struct.
+///
+/// ```ignore
+/// #[derive(Debug, MoveStruct)]
+/// #[move_struct(package = "0x2", module = "kiosk")]
+/// pub struct KioskOwnerCap {
+///     pub id: UID,
+///     #[move_struct(rename = "for")]
+///     pub for_: ID,
+/// }
+/// ```
+///
+/// `name` defaults to the Rust struct name. Type parameters become the type arguments of the
+/// struct tag, in order, and fields marked `#[move_struct(skip)]` (e.g. a `PhantomData`) are left
+/// out of the serialized layout and filled with `Default::default()` when deserializing.
+///
+/// The generated code refers to `::common`. Crates depending on it under another name, or through
+/// a re-export, pass its path with `#[move_struct(crate = "::sui_common")]`.
+#[proc_macro_derive(MoveStruct, attributes(move_struct))]
+pub fn derive_move_struct(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Field {
+    ident: Ident,
+    ty: Type,
+    move_name: String,
+    skip: bool,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let (package, module, name, krate) = struct_attributes(&input)?;
+    let fields = fields(&input)?;
+
+    let type_params: Vec<&Ident> = input
+        .generics
+        .params
+        .iter()
+        .map(|param| match param {
+            GenericParam::Type(param) => Ok(&param.ident),
+            _ => Err(Error::new_spanned(
+                param,
+                "Only type parameters are supported, Move structs own all their data",
+            )),
+        })
+        .collect::<syn::Result<_>>()?;
+
+    let ident = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    let type_predicates = where_clause.map(|clause| &clause.predicates);
+    let fn_type = quote!(fn() -> (#(#type_params,)*));
+
+    let kept: Vec<&Field> = fields.iter().filter(|field| !field.skip).collect();
+    let kept_idents: Vec<&Ident> = kept.iter().map(|field| &field.ident).collect();
+    let kept_types: Vec<&Type> = kept.iter().map(|field| &field.ty).collect();
+    let kept_names: Vec<&String> = kept.iter().map(|field| &field.move_name).collect();
+    let skipped_idents: Vec<&Ident> = fields
+        .iter()
+        .filter(|field| field.skip)
+        .map(|field| &field.ident)
+        .collect();
+
+    // Only fields mentioning a type parameter need a bound, the others are checked as is
+    let generic_types: Vec<&Type> = kept_types
+        .iter()
+        .copied()
+        .filter(|ty| mentions_any(quote!(#ty), &type_params))
+        .collect();
+    let serialize_bound = bound(&generic_types, quote!(#krate::__private::serde::Serialize));
+    let deserialize_bound = bound(
+        &generic_types,
+        quote!(#krate::__private::serde::Deserialize<'de>),
+    );
+    let serde_crate = LitStr::new(
+        &quote!(#krate::__private::serde).to_string(),
+        Span::call_site(),
+    );
+
+    let mut de_generics = input.generics.clone();
+    de_generics.params.insert(0, parse_quote!('de));
+    let (de_impl_generics, _, _) = de_generics.split_for_impl();
+
+    let shadow = format_ident!("__{}Shadow", ident);
+    let shadow_ref = format_ident!("__{}ShadowRef", ident);
+
+    Ok(quote! {
+        const _: () = {
+            use #krate::__private::{
+                move_core_types::{
+                    account_address::AccountAddress,
+                    identifier::Identifier,
+                    language_storage::{StructTag, TypeTag},
+                },
+                serde as __serde,
+            };
+
+            impl #impl_generics #krate::MoveStruct for #ident #type_generics
+            where
+                #(#type_params: #krate::MoveType,)*
+                #type_predicates
+            {
+                fn struct_tag() -> StructTag {
+                    StructTag {
+                        address: AccountAddress::from_hex_literal(#package)
+                            .expect("Package address checked by the derive"),
+                        module: Identifier::new(#module)
+                            .expect("Module name checked by the derive"),
+                        name: Identifier::new(#name).expect("Struct name checked by the derive"),
+                        type_params: vec![
+                            #(<#type_params as #krate::MoveType>::type_tag()),*
+                        ],
+                    }
+                }
+            }
+
+            impl #impl_generics #krate::MoveType for #ident #type_generics
+            where
+                #(#type_params: #krate::MoveType,)*
+                #type_predicates
+            {
+                fn type_tag() -> TypeTag {
+                    TypeTag::Struct(Box::new(
+                        <Self as #krate::MoveStruct>::struct_tag(),
+                    ))
+                }
+            }
+
+            #[derive(__serde::Serialize)]
+            #[serde(crate = #serde_crate, rename = #name)]
+            #[serde(bound(serialize = #serialize_bound))]
+            struct #shadow_ref<'__a, #(#type_params),*> {
+                #(
+                    #[serde(rename = #kept_names)]
+                    #kept_idents: &'__a #kept_types,
+                )*
+                #[serde(skip)]
+                __type: ::std::marker::PhantomData<#fn_type>,
+            }
+
+            #[derive(__serde::Deserialize)]
+            #[serde(crate = #serde_crate, rename = #name)]
+            #[serde(bound(deserialize = #deserialize_bound))]
+            struct #shadow<#(#type_params),*> {
+                #(
+                    #[serde(rename = #kept_names)]
+                    #kept_idents: #kept_types,
+                )*
+                #[serde(skip)]
+                __type: ::std::marker::PhantomData<#fn_type>,
+            }
+
+            impl #impl_generics __serde::Serialize for #ident #type_generics
+            where
+                #(#generic_types: __serde::Serialize,)*
+                #type_predicates
+            {
+                fn serialize<S: __serde::Serializer>(
+                    &self,
+                    serializer: S,
+                ) -> ::std::result::Result<S::Ok, S::Error> {
+                    __serde::Serialize::serialize(
+                        &#shadow_ref::<#(#type_params),*> {
+                            #(#kept_idents: &self.#kept_idents,)*
+                            __type: ::std::marker::PhantomData,
+                        },
+                        serializer,
+                    )
+                }
+            }
+
+            impl #de_impl_generics __serde::Deserialize<'de> for #ident #type_generics
+            where
+                #(#generic_types: __serde::Deserialize<'de>,)*
+                #type_predicates
+            {
+                fn deserialize<D: __serde::Deserializer<'de>>(
+                    deserializer: D,
+                ) -> ::std::result::Result<Self, D::Error> {
+                    let shadow: #shadow<#(#type_params),*> =
+                        __serde::Deserialize::deserialize(deserializer)?;
+                    Ok(Self {
+                        #(#kept_idents: shadow.#kept_idents,)*
+                        #(#skipped_idents: ::std::default::Default::default(),)*
+                    })
+                }
+            }
+        };
+    })
+}
+
+/// `package`, `module` and `name` from `#[move_struct(..)]`, validated so that building the
+/// struct tag cannot fail at runtime, and the path of the `common` crate
+fn struct_attributes(input: &DeriveInput) -> syn::Result<(LitStr, LitStr, LitStr, Path)> {
+    let mut package = None;
+    let mut module = None;
+    let mut name = None;
+    let mut krate: Option<Path> = None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("move_struct"))
+    {
+        attr.parse_nested_meta(|meta| {
+            let value: LitStr = meta.value()?.parse()?;
+            if meta.path.is_ident("package") {
+                check_address(&value)?;
+                package = Some(value);
+            } else if meta.path.is_ident("module") {
+                check_identifier(&value)?;
+                module = Some(value);
+            } else if meta.path.is_ident("name") {
+                check_identifier(&value)?;
+                name = Some(value);
+            } else if meta.path.is_ident("crate") {
+                krate = Some(value.parse()?);
+            } else {
+                return Err(meta.error("Expected `package`, `module`, `name` or `crate`"));
+            }
+            Ok(())
+        })?;
+    }
+
+    let missing = |attribute: &str| {
+        Error::new(
+            Span::call_site(),
+            format!("Missing `#[move_struct({} = \"..\")]`", attribute),
+        )
+    };
+    let package = package.ok_or_else(|| missing("package"))?;
+    let module = module.ok_or_else(|| missing("module"))?;
+    let name = match name {
+        Some(name) => name,
+        None => LitStr::new(&input.ident.unraw().to_string(), input.ident.span()),
+    };
+
+    let krate = krate.unwrap_or_else(|| parse_quote!(::common));
+
+    Ok((package, module, name, krate))
+}
+
+fn fields(input: &DeriveInput) -> syn::Result<Vec<Field>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new_spanned(
+            &input.ident,
+            "Only structs mirror Move structs",
+        ));
+    };
+    let Fields::Named(named) = &data.fields else {
+        return Err(Error::new_spanned(
+            &data.fields,
+            "Move structs have named fields",
+        ));
+    };
+
+    named
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field.ident.clone().expect("Named fields have an ident");
+            let mut move_name = ident.unraw().to_string();
+            let mut skip = false;
+
+            for attr in field
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident("move_struct"))
+            {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("rename") {
+                        let value: LitStr = meta.value()?.parse()?;
+                        check_identifier(&value)?;
+                        move_name = value.value();
+                    } else if meta.path.is_ident("skip") {
+                        skip = true;
+                    } else {
+                        return Err(meta.error("Expected `rename` or `skip`"));
+                    }
+                    Ok(())
+                })?;
+            }
+
+            Ok(Field {
+                ident,
+                ty: field.ty.clone(),
+                move_name,
+                skip,
+            })
+        })
+        .collect()
+}
+
+fn check_address(value: &LitStr) -> syn::Result<()> {
+    let address = value.value();
+    let valid = match address.strip_prefix("0x") {
+        Some(hex) => {
+            !hex.is_empty() && hex.len() <= 64 && hex.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    };
+    if !valid {
+        return Err(Error::new_spanned(
+            value,
+            format!("{:?} is not a hex address literal such as \"0x2\"", address),
+        ));
+    }
+    Ok(())
+}
+
+/// Same rules as `move_core_types::identifier::is_valid`
+fn check_identifier(value: &LitStr) -> syn::Result<()> {
+    let identifier = value.value();
+    let mut chars = identifier.chars();
+    let valid = match chars.next() {
+        Some('a'..='z' | 'A'..='Z') => chars.all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        Some('_') => {
+            let rest = chars.as_str();
+            !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        }
+        _ => false,
+    };
+    if !valid {
+        return Err(Error::new_spanned(
+            value,
+            format!("{:?} is not a valid Move identifier", identifier),
+        ));
+    }
+    Ok(())
+}
+
+fn mentions_any(tokens: TokenStream2, idents: &[&Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => idents.iter().any(|param| **param == ident),
+        TokenTree::Group(group) => mentions_any(group.stream(), idents),
+        _ => false,
+    })
+}
+
+/// Serde `bound` attribute value requiring `trait_` of every given type
+fn bound(types: &[&Type], trait_: TokenStream2) -> LitStr {
+    let predicates = types.iter().map(|ty| quote!(#ty: #trait_));
+    LitStr::new(&quote!(#(#predicates),*).to_string(), Span::call_site())
+}
@@ -0,0 +1,111 @@
+//! Serialized layout and type tags of `#[derive(MoveStruct)]` mirrors.
+
+// Makes `common` available under another name, as a crate depending on it as `sui-common` would
+extern crate common as sui_common;
+
+use std::{marker::PhantomData, str::FromStr};
+
+use common::{framework::SUI, MoveStruct, MoveType};
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    id::ID,
+    TypeTag,
+};
+
+#[derive(Debug, MoveStruct)]
+#[move_struct(package = "0xabc", module = "market")]
+struct Listing<T> {
+    price: u64,
+    #[move_struct(rename = "for")]
+    for_: ID,
+    seller: SuiAddress,
+    #[move_struct(skip)]
+    _item: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug, MoveStruct)]
+#[move_struct(package = "0xabc", module = "market", name = "Market")]
+struct MarketMirror {
+    listings: Vec<Listing<SUI>>,
+    fees: Vec<u64>,
+}
+
+#[derive(Debug, sui_common::MoveStruct)]
+#[move_struct(crate = "::sui_common", package = "0xabc", module = "market")]
+struct Renamed {
+    value: u64,
+}
+
+fn listing() -> Listing<SUI> {
+    Listing {
+        price: 42,
+        for_: ID::new(ObjectID::from_single_byte(7)),
+        seller: SuiAddress::from(ObjectID::from_single_byte(9)),
+        _item: PhantomData,
+    }
+}
+
+#[test]
+fn bcs_layout_is_the_field_order() {
+    let listing = listing();
+    let bytes = bcs::to_bytes(&listing).unwrap();
+    assert_eq!(
+        bytes,
+        bcs::to_bytes(&(listing.price, &listing.for_, listing.seller)).unwrap()
+    );
+
+    let decoded: Listing<SUI> = bcs::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.price, listing.price);
+    assert_eq!(decoded.for_, listing.for_);
+    assert_eq!(decoded.seller, listing.seller);
+}
+
+#[test]
+fn nested_mirrors_round_trip() {
+    let market = MarketMirror {
+        listings: vec![listing(), listing()],
+        fees: vec![1, 2, 3],
+    };
+    let bytes = bcs::to_bytes(&market).unwrap();
+    let decoded: MarketMirror = bcs::from_bytes(&bytes).unwrap();
+    assert_eq!(decoded.listings.len(), 2);
+    assert_eq!(decoded.listings[1].price, 42);
+    assert_eq!(decoded.fees, [1, 2, 3]);
+    assert_eq!(bcs::to_bytes(&decoded).unwrap(), bytes);
+}
+
+#[test]
+fn fields_are_renamed_or_skipped() {
+    let json = serde_json::to_value(listing()).unwrap();
+    assert_eq!(json["for"], serde_json::to_value(listing().for_).unwrap());
+    assert!(json.get("for_").is_none());
+    assert!(json.get("_item").is_none());
+    assert_eq!(json.as_object().unwrap().len(), 3);
+}
+
+#[test]
+fn type_tags() {
+    assert_eq!(
+        Listing::<SUI>::type_tag(),
+        TypeTag::from_str("0xabc::market::Listing<0x2::sui::SUI>").unwrap()
+    );
+    assert_eq!(
+        Listing::<Listing<SUI>>::type_tag(),
+        TypeTag::from_str("0xabc::market::Listing<0xabc::market::Listing<0x2::sui::SUI>>").unwrap()
+    );
+    assert_eq!(
+        MarketMirror::type_tag(),
+        TypeTag::from_str("0xabc::market::Market").unwrap()
+    );
+    assert!(MarketMirror::struct_tag().type_params.is_empty());
+}
+
+#[test]
+fn crate_path_override() {
+    assert_eq!(
+        Renamed::type_tag(),
+        TypeTag::from_str("0xabc::market::Renamed").unwrap()
+    );
+    let renamed: Renamed = bcs::from_bytes(&bcs::to_bytes(&Renamed { value: 5 }).unwrap()).unwrap();
+    assert_eq!(renamed.value, 5);
+}
@@ -0,0 +1,9 @@
+use common::MoveStruct;
+
+#[derive(MoveStruct)]
+#[move_struct(package = "0xzz", module = "coin")]
+pub struct Coin {
+    pub value: u64,
+}
+
+fn main() {}
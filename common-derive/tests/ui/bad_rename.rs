@@ -0,0 +1,10 @@
+use common::MoveStruct;
+
+#[derive(MoveStruct)]
+#[move_struct(package = "0x2", module = "kiosk")]
+pub struct KioskOwnerCap {
+    #[move_struct(rename = "for-")]
+    pub for_: u64,
+}
+
+fn main() {}
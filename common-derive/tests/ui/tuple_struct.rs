@@ -0,0 +1,7 @@
+use common::MoveStruct;
+
+#[derive(MoveStruct)]
+#[move_struct(package = "0x2", module = "balance")]
+pub struct Balance(u64);
+
+fn main() {}
@@ -0,0 +1,10 @@
+use common::MoveStruct;
+
+#[derive(MoveStruct)]
+#[move_struct(package = "0x2", module = "coin")]
+pub struct Coin {
+    #[move_struct(skipp)]
+    pub value: u64,
+}
+
+fn main() {}
@@ -0,0 +1,181 @@
+//! Payload and latency of `fetch_sorted_gas_coins`, which reads full coin objects, against
+//! `fetch_sorted_gas_coin_refs`, which goes through the coin read API.
+//!
+//! Both run against the replay of `benches/fixtures/gas_coins.json`, recorded from a fullnode with
+//!
+//! ```sh
+//! SUI_RPC_FIXTURE_MODE=record SUI_SENDER=0x... cargo bench -p common --features fake
+//! ```
+//!
+//! Until that file exists, the traffic of a synthetic node holding `SYNTHETIC_COINS` coins is
+//! recorded and replayed instead, which the report says.
+
+use std::{
+    future::Future,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Context};
+use common::{
+    fake::{object_id, sui_coin, sui_coin_object},
+    fetch_sorted_gas_coin_refs, fetch_sorted_gas_coins,
+    fixtures::Fixture,
+    FakeNode, FixtureMode, FixtureServer, Network,
+};
+use serde_json::Value;
+use sui_sdk::SuiClientBuilder;
+use sui_types::base_types::{ObjectID, SuiAddress};
+
+const ITERATIONS: usize = 20;
+const SYNTHETIC_COINS: u64 = 500;
+
+const OWNED_OBJECTS_METHOD: &str = "suix_getOwnedObjects";
+const COINS_METHOD: &str = "suix_getCoins";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let recording = PathBuf::from(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/benches/fixtures/gas_coins.json"
+    ));
+
+    let (path, source) = match FixtureMode::from_env(&recording)? {
+        FixtureMode::Record(path) => {
+            let network = Network::from_str(
+                &std::env::var("SUI_NETWORK").unwrap_or_else(|_| "mainnet".to_string()),
+            )?;
+            let sender = std::env::var("SUI_SENDER")
+                .context("SUI_SENDER is required to record")?
+                .parse()?;
+            let server =
+                FixtureServer::start(FixtureMode::Record(path.clone()), network.rpc_url()).await?;
+            record(server, sender).await?;
+            (path, format!("recorded from {}", network.rpc_url()))
+        }
+        FixtureMode::Replay(path) => (path, "recorded".to_string()),
+        FixtureMode::Live if recording.exists() => (recording, "recorded".to_string()),
+        FixtureMode::Live => {
+            let path = Path::new(env!("CARGO_TARGET_TMPDIR")).join("gas_coins-synthetic.json");
+            let sender = SuiAddress::from(ObjectID::from_single_byte(0xab));
+            let node = Arc::new(synthetic_node(sender, SYNTHETIC_COINS));
+            let server =
+                FixtureServer::start_with_node(FixtureMode::Record(path.clone()), node).await?;
+            record(server, sender).await?;
+            (path, format!("synthetic, {} coins", SYNTHETIC_COINS))
+        }
+    };
+
+    let fixture: Fixture = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    let owner = fixture
+        .interactions
+        .iter()
+        .find(|interaction| interaction.method == COINS_METHOD)
+        .and_then(|interaction| interaction.params.get(0).cloned())
+        .ok_or_else(|| anyhow!("No {} call in {}", COINS_METHOD, path.display()))?;
+    let sender: SuiAddress = serde_json::from_value(owner)?;
+
+    let server = FixtureServer::start(FixtureMode::Replay(path.clone()), "").await?;
+    let rpc_client = SuiClientBuilder::default().build(server.url()).await?;
+
+    println!("Replaying {} ({})", path.display(), source);
+    println!(
+        "{:<28} {:>6} {:>12} {:>12} {:>12}",
+        "", "pages", "payload", "median", "min"
+    );
+    let full = measure(|| fetch_sorted_gas_coins(&rpc_client, &sender)).await?;
+    report(
+        "fetch_sorted_gas_coins",
+        payload(&fixture, OWNED_OBJECTS_METHOD),
+        &full,
+    );
+    let lean = measure(|| fetch_sorted_gas_coin_refs(&rpc_client, &sender)).await?;
+    report(
+        "fetch_sorted_gas_coin_refs",
+        payload(&fixture, COINS_METHOD),
+        &lean,
+    );
+
+    server.finish().await
+}
+
+/// Record one run of both functions through the recording `server`
+async fn record(server: FixtureServer, sender: SuiAddress) -> anyhow::Result<()> {
+    let rpc_client = SuiClientBuilder::default().build(server.url()).await?;
+    let coins = fetch_sorted_gas_coins(&rpc_client, &sender).await;
+    let coin_refs = fetch_sorted_gas_coin_refs(&rpc_client, &sender).await;
+    server.finish().await?;
+
+    let (coins, coin_refs) = (coins?, coin_refs?);
+    anyhow::ensure!(
+        coins.len() == coin_refs.len(),
+        "{} coin objects but {} coin refs recorded",
+        coins.len(),
+        coin_refs.len()
+    );
+    Ok(())
+}
+
+/// Durations of `ITERATIONS` calls after a warm-up one, sorted
+async fn measure<T, F: Future<Output = common::Result<T>>>(
+    call: impl Fn() -> F,
+) -> anyhow::Result<Vec<Duration>> {
+    call().await?;
+    let mut durations = Vec::with_capacity(ITERATIONS);
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        call().await?;
+        durations.push(start.elapsed());
+    }
+    durations.sort();
+    Ok(durations)
+}
+
+/// Number of pages and total size of the responses recorded for `method`
+fn payload(fixture: &Fixture, method: &str) -> (usize, usize) {
+    let responses: Vec<&Value> = fixture
+        .interactions
+        .iter()
+        .filter(|interaction| interaction.method == method)
+        .map(|interaction| &interaction.response)
+        .collect();
+    let bytes = responses
+        .iter()
+        .map(|response| response.to_string().len())
+        .sum();
+    (responses.len(), bytes)
+}
+
+fn report(name: &str, (pages, bytes): (usize, usize), durations: &[Duration]) {
+    println!(
+        "{:<28} {:>6} {:>10} B {:>12?} {:>12?}",
+        name,
+        pages,
+        bytes,
+        durations[durations.len() / 2],
+        durations[0]
+    );
+}
+
+/// `FakeNode` holding `coins` SUI coins for `owner`, as both full objects and coin read API entries
+fn synthetic_node(owner: SuiAddress, coins: u64) -> FakeNode {
+    let balances: Vec<(ObjectID, u64)> =
+        (1..=coins).map(|i| (object_id(i), i * 1_000_000)).collect();
+    FakeNode::new()
+        .with_owned_objects(
+            owner,
+            balances
+                .iter()
+                .map(|(id, balance)| sui_coin_object(*id, *balance))
+                .collect(),
+        )
+        .with_coins(
+            owner,
+            balances
+                .iter()
+                .map(|(id, balance)| sui_coin(*id, *balance))
+                .collect(),
+        )
+}
@@ -0,0 +1,461 @@
+//! What the PTB analysis needs to know about Move functions and structs: how each argument is
+//! taken, and which values can be copied or dropped.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use move_core_types::account_address::AccountAddress;
+use sui_sdk::rpc_types::{SuiMoveAbility, SuiMoveNormalizedModule, SuiMoveNormalizedType};
+use sui_types::{base_types::ObjectID, TypeTag, SUI_FRAMEWORK_ADDRESS};
+
+use crate::framework;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Usage {
+    ByValue,
+    ByRef,
+    ByMutRef,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Abilities {
+    pub copy: bool,
+    pub drop: bool,
+}
+
+impl Abilities {
+    pub const COPY_DROP: Self = Self {
+        copy: true,
+        drop: true,
+    };
+    pub const NONE: Self = Self {
+        copy: false,
+        drop: false,
+    };
+
+    fn intersect(self, other: Self) -> Self {
+        Self {
+            copy: self.copy && other.copy,
+            drop: self.drop && other.drop,
+        }
+    }
+}
+
+/// Move type of a parameter or return value, down to what decides its abilities
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AbiType {
+    /// Integers, `bool`, `address` and `signer`
+    Primitive,
+    Struct {
+        address: AccountAddress,
+        module: String,
+        name: String,
+        type_arguments: Vec<AbiType>,
+    },
+    Vector(Box<AbiType>),
+    TypeParameter(u16),
+}
+
+impl AbiType {
+    /// Struct of the Sui framework
+    pub fn framework(module: &str, name: &str, type_arguments: Vec<AbiType>) -> Self {
+        AbiType::Struct {
+            address: SUI_FRAMEWORK_ADDRESS,
+            module: module.to_string(),
+            name: name.to_string(),
+            type_arguments,
+        }
+    }
+}
+
+/// Parameters as passed by a PTB, i.e. without the trailing `TxContext`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionSignature {
+    pub parameters: Vec<(Usage, AbiType)>,
+    pub returns: Vec<AbiType>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructAbilities {
+    pub abilities: Abilities,
+    /// Whether each type parameter is phantom, i.e. does not restrict the struct's abilities
+    pub phantom: Vec<bool>,
+}
+
+type QualifiedName = (AccountAddress, String, String);
+
+/// Function signatures and struct abilities, from the bundled framework subset and any supplied
+/// normalized modules
+#[derive(Debug, Clone, Default)]
+pub struct Abi {
+    functions: HashMap<QualifiedName, FunctionSignature>,
+    structs: HashMap<QualifiedName, StructAbilities>,
+}
+
+impl Abi {
+    /// The framework functions with a descriptor in `framework`, and the structs they use
+    pub fn framework() -> Self {
+        let mut abi = Self::default();
+        framework::register_abi(&mut abi);
+        abi
+    }
+
+    pub fn add_function(
+        &mut self,
+        address: AccountAddress,
+        module: &str,
+        function: &str,
+        signature: FunctionSignature,
+    ) {
+        self.functions.insert(
+            (address, module.to_string(), function.to_string()),
+            signature,
+        );
+    }
+
+    pub fn add_struct(
+        &mut self,
+        address: AccountAddress,
+        module: &str,
+        name: &str,
+        abilities: StructAbilities,
+    ) {
+        self.structs
+            .insert((address, module.to_string(), name.to_string()), abilities);
+    }
+
+    /// Add the structs and functions of normalized modules, e.g. saved with `move-bindgen fetch`
+    pub fn add_modules(&mut self, modules: &[SuiMoveNormalizedModule]) -> anyhow::Result<()> {
+        for module in modules {
+            let address = AccountAddress::from_hex_literal(&module.address)
+                .with_context(|| format!("Invalid module address {}", module.address))?;
+
+            for (name, struct_) in &module.structs {
+                let abilities = &struct_.abilities.abilities;
+                self.add_struct(
+                    address,
+                    &module.name,
+                    name,
+                    StructAbilities {
+                        abilities: Abilities {
+                            copy: abilities.iter().any(|a| matches!(a, SuiMoveAbility::Copy)),
+                            drop: abilities.iter().any(|a| matches!(a, SuiMoveAbility::Drop)),
+                        },
+                        phantom: struct_
+                            .type_parameters
+                            .iter()
+                            .map(|parameter| parameter.is_phantom)
+                            .collect(),
+                    },
+                );
+            }
+
+            for (name, function) in &module.exposed_functions {
+                let mut parameters = vec![];
+                for parameter in &function.parameters {
+                    let (usage, type_) = match parameter {
+                        SuiMoveNormalizedType::Reference(inner) => (Usage::ByRef, inner.as_ref()),
+                        SuiMoveNormalizedType::MutableReference(inner) => {
+                            (Usage::ByMutRef, inner.as_ref())
+                        }
+                        type_ => (Usage::ByValue, type_),
+                    };
+                    let type_ = abi_type(type_)?;
+                    if !is_tx_context(&type_) {
+                        parameters.push((usage, type_));
+                    }
+                }
+                let returns = function
+                    .return_
+                    .iter()
+                    .map(abi_type)
+                    .collect::<anyhow::Result<_>>()?;
+
+                self.add_function(
+                    address,
+                    &module.name,
+                    name,
+                    FunctionSignature {
+                        parameters,
+                        returns,
+                    },
+                );
+            }
+        }
+        Ok(())
+    }
+
+    pub fn function(
+        &self,
+        package: ObjectID,
+        module: &str,
+        function: &str,
+    ) -> Option<&FunctionSignature> {
+        self.functions.get(&(
+            AccountAddress::from(package),
+            module.to_string(),
+            function.to_string(),
+        ))
+    }
+
+    /// Abilities of a concrete type, `None` if it involves a struct this ABI does not know
+    pub fn abilities(&self, type_tag: &TypeTag) -> Option<Abilities> {
+        match type_tag {
+            TypeTag::Vector(inner) => self.abilities(inner),
+            TypeTag::Struct(struct_tag) => self.struct_abilities(
+                struct_tag.address,
+                struct_tag.module.as_str(),
+                struct_tag.name.as_str(),
+                &struct_tag
+                    .type_params
+                    .iter()
+                    .map(|type_param| self.abilities(type_param))
+                    .collect::<Vec<_>>(),
+            ),
+            _ => Some(Abilities::COPY_DROP),
+        }
+    }
+
+    /// Abilities of a signature type instantiated with `type_arguments`
+    pub fn instantiated_abilities(
+        &self,
+        type_: &AbiType,
+        type_arguments: &[TypeTag],
+    ) -> Option<Abilities> {
+        match type_ {
+            AbiType::Primitive => Some(Abilities::COPY_DROP),
+            AbiType::Vector(inner) => self.instantiated_abilities(inner, type_arguments),
+            AbiType::TypeParameter(index) => self.abilities(type_arguments.get(*index as usize)?),
+            AbiType::Struct {
+                address,
+                module,
+                name,
+                type_arguments: struct_type_arguments,
+            } => self.struct_abilities(
+                *address,
+                module,
+                name,
+                &struct_type_arguments
+                    .iter()
+                    .map(|type_| self.instantiated_abilities(type_, type_arguments))
+                    .collect::<Vec<_>>(),
+            ),
+        }
+    }
+
+    fn struct_abilities(
+        &self,
+        address: AccountAddress,
+        module: &str,
+        name: &str,
+        type_arguments: &[Option<Abilities>],
+    ) -> Option<Abilities> {
+        let struct_ = self
+            .structs
+            .get(&(address, module.to_string(), name.to_string()))?;
+
+        // Non-phantom type arguments restrict the declared abilities
+        let mut abilities = struct_.abilities;
+        for (type_argument, phantom) in type_arguments.iter().zip(&struct_.phantom) {
+            if !phantom {
+                abilities = abilities.intersect((*type_argument)?);
+            }
+        }
+        Some(abilities)
+    }
+}
+
+fn abi_type(type_: &SuiMoveNormalizedType) -> anyhow::Result<AbiType> {
+    Ok(match type_ {
+        SuiMoveNormalizedType::Struct {
+            address,
+            module,
+            name,
+            type_arguments,
+        } => AbiType::Struct {
+            address: AccountAddress::from_hex_literal(address)
+                .with_context(|| format!("Invalid struct address {}", address))?,
+            module: module.clone(),
+            name: name.clone(),
+            type_arguments: type_arguments
+                .iter()
+                .map(abi_type)
+                .collect::<anyhow::Result<_>>()?,
+        },
+        SuiMoveNormalizedType::Vector(inner) => AbiType::Vector(Box::new(abi_type(inner)?)),
+        SuiMoveNormalizedType::TypeParameter(index) => AbiType::TypeParameter(*index),
+        SuiMoveNormalizedType::Reference(inner)
+        | SuiMoveNormalizedType::MutableReference(inner) => abi_type(inner)?,
+        _ => AbiType::Primitive,
+    })
+}
+
+fn is_tx_context(type_: &AbiType) -> bool {
+    matches!(
+        type_,
+        AbiType::Struct { address, module, name, .. }
+            if *address == SUI_FRAMEWORK_ADDRESS && module == "tx_context" && name == "TxContext"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn struct_type(address: &str, module: &str, name: &str, type_arguments: Vec<Value>) -> Value {
+        json!({ "Struct": {
+            "address": address,
+            "module": module,
+            "name": name,
+            "typeArguments": type_arguments,
+        } })
+    }
+
+    fn struct_(abilities: &[&str], phantom: &[bool]) -> Value {
+        let type_parameters: Vec<_> = phantom
+            .iter()
+            .map(
+                |is_phantom| json!({ "constraints": { "abilities": [] }, "isPhantom": is_phantom }),
+            )
+            .collect();
+        json!({
+            "abilities": { "abilities": abilities },
+            "typeParameters": type_parameters,
+            "fields": [],
+        })
+    }
+
+    fn function(parameters: Vec<Value>, returns: Vec<Value>) -> Value {
+        json!({
+            "visibility": "Public",
+            "isEntry": false,
+            "typeParameters": [{ "abilities": [] }],
+            "parameters": parameters,
+            "return": returns,
+        })
+    }
+
+    /// `0xabc::vault`, holding coins of a phantom type and handing out droppable tickets
+    fn vault_module(address: &str) -> SuiMoveNormalizedModule {
+        let vault = struct_type(
+            address,
+            "vault",
+            "Vault",
+            vec![json!({ "TypeParameter": 0 })],
+        );
+        let coin = struct_type("0x2", "coin", "Coin", vec![json!({ "TypeParameter": 0 })]);
+        let ticket = struct_type(address, "vault", "Ticket", vec![]);
+        let tx_context = struct_type("0x2", "tx_context", "TxContext", vec![]);
+        serde_json::from_value(json!({
+            "fileFormatVersion": 6,
+            "address": address,
+            "name": "vault",
+            "friends": [],
+            "structs": {
+                "Vault": struct_(&["Key"], &[true]),
+                "Ticket": struct_(&["Drop"], &[]),
+                "Pair": struct_(&["Copy", "Drop", "Store"], &[false]),
+            },
+            "exposedFunctions": {
+                "deposit": function(
+                    vec![
+                        json!({ "MutableReference": vault }),
+                        coin,
+                        json!({ "MutableReference": tx_context }),
+                    ],
+                    vec![ticket],
+                ),
+                "peek": function(vec![json!({ "Reference": vault })], vec![json!("U64")]),
+            },
+        }))
+        .unwrap()
+    }
+
+    fn vault_abi() -> Abi {
+        let mut abi = Abi::framework();
+        abi.add_modules(&[vault_module("0xabc")]).unwrap();
+        abi
+    }
+
+    fn vault_type(name: &str, type_params: &str) -> TypeTag {
+        TypeTag::from_str(&format!("0xabc::vault::{}{}", name, type_params)).unwrap()
+    }
+
+    #[test]
+    fn modules_add_their_functions_without_tx_context() {
+        let abi = vault_abi();
+        let package = ObjectID::from_hex_literal("0xabc").unwrap();
+        let address = AccountAddress::from_hex_literal("0xabc").unwrap();
+        let vault = AbiType::Struct {
+            address,
+            module: "vault".to_string(),
+            name: "Vault".to_string(),
+            type_arguments: vec![AbiType::TypeParameter(0)],
+        };
+
+        assert_eq!(
+            abi.function(package, "vault", "deposit"),
+            Some(&FunctionSignature {
+                parameters: vec![
+                    (Usage::ByMutRef, vault.clone()),
+                    (
+                        Usage::ByValue,
+                        AbiType::framework("coin", "Coin", vec![AbiType::TypeParameter(0)])
+                    ),
+                ],
+                returns: vec![AbiType::Struct {
+                    address,
+                    module: "vault".to_string(),
+                    name: "Ticket".to_string(),
+                    type_arguments: vec![],
+                }],
+            })
+        );
+        assert_eq!(
+            abi.function(package, "vault", "peek"),
+            Some(&FunctionSignature {
+                parameters: vec![(Usage::ByRef, vault)],
+                returns: vec![AbiType::Primitive],
+            })
+        );
+        assert_eq!(abi.function(package, "vault", "withdraw"), None);
+    }
+
+    #[test]
+    fn modules_add_their_struct_abilities() {
+        let abi = vault_abi();
+        let drop = Abilities {
+            copy: false,
+            drop: true,
+        };
+
+        assert_eq!(
+            abi.abilities(&vault_type("Vault", "<0x2::sui::SUI>")),
+            Some(Abilities::NONE)
+        );
+        assert_eq!(abi.abilities(&vault_type("Ticket", "")), Some(drop));
+        // Non-phantom type arguments restrict the declared abilities
+        assert_eq!(
+            abi.abilities(&vault_type("Pair", "<u64>")),
+            Some(Abilities::COPY_DROP)
+        );
+        assert_eq!(
+            abi.abilities(&vault_type("Pair", "<0x2::coin::Coin<0x2::sui::SUI>>")),
+            Some(Abilities::NONE)
+        );
+        assert_eq!(
+            abi.abilities(&vault_type("Pair", "<0xdef::m::Unknown>")),
+            None
+        );
+    }
+
+    #[test]
+    fn invalid_module_addresses_are_errors() {
+        let mut abi = Abi::default();
+        assert!(abi.add_modules(&[vault_module("0xnot-hex")]).is_err());
+    }
+}
@@ -0,0 +1,379 @@
+//! Gas budgets derived from a dry run of the transaction rather than hard-coded.
+
+use anyhow::{bail, ensure};
+use sui_sdk::rpc_types::SuiTransactionBlockEffectsAPI;
+use sui_types::{
+    base_types::{ObjectRef, SuiAddress},
+    gas::GasCostSummary,
+    transaction::{ProgrammableTransaction, TransactionData},
+};
+
+use crate::{config::DEFAULT_GAS_BUDGET, limits::MAX_GAS_BUDGET, NodeApi};
+
+/// Max number of dry runs of `estimate_gas_budget_until_stable` before giving up
+const MAX_STABILIZATION_ROUNDS: usize = 5;
+
+/// How the budget is derived from the cost of a dry run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GasBudgetPolicy {
+    /// Budget of the dry run, high enough for it not to run out of gas. The gas payment must
+    /// cover it.
+    pub provisional_budget: u64,
+    /// Margin over the dry run cost, in percent
+    pub margin_percent: u64,
+    /// Lower bound of the margin, absorbing e.g. a change of computation bucket between the dry
+    /// run and the execution
+    pub min_margin: u64,
+}
+
+impl Default for GasBudgetPolicy {
+    fn default() -> Self {
+        Self {
+            provisional_budget: DEFAULT_GAS_BUDGET,
+            margin_percent: 20,
+            min_margin: 1_000_000,
+        }
+    }
+}
+
+impl GasBudgetPolicy {
+    /// Budget for a transaction costing `gas_used`. The budget has to cover the computation and
+    /// storage costs in full, the storage rebate only being refunded afterwards. Fails when the
+    /// budget with its margin exceeds `MAX_GAS_BUDGET`, rather than returning less than needed.
+    pub fn budget(&self, gas_used: &GasCostSummary) -> anyhow::Result<u64> {
+        let cost = gas_used
+            .computation_cost
+            .saturating_add(gas_used.storage_cost);
+        let margin = (cost.saturating_mul(self.margin_percent) / 100).max(self.min_margin);
+        let budget = cost.saturating_add(margin);
+        ensure!(
+            budget <= MAX_GAS_BUDGET,
+            "Gas budget {} for a cost of {} exceeds the max of {}",
+            budget,
+            cost,
+            MAX_GAS_BUDGET
+        );
+        Ok(budget)
+    }
+}
+
+/// Budget derived from a dry run, along with the costs it was derived from
+#[derive(Debug, Clone)]
+pub struct GasBudgetEstimate {
+    pub budget: u64,
+    pub gas_cost_summary: GasCostSummary,
+}
+
+impl GasBudgetEstimate {
+    /// What the sender actually pays, negative when the storage rebate exceeds the costs
+    pub fn net_cost(&self) -> i64 {
+        self.gas_cost_summary.net_gas_usage()
+    }
+}
+
+/// Estimate the budget of `pt` with the default policy
+pub async fn estimate_gas_budget(
+    rpc_client: &impl NodeApi,
+    sender: SuiAddress,
+    pt: ProgrammableTransaction,
+    gas_payment: Vec<ObjectRef>,
+) -> anyhow::Result<GasBudgetEstimate> {
+    estimate_gas_budget_with_policy(
+        rpc_client,
+        sender,
+        pt,
+        gas_payment,
+        &GasBudgetPolicy::default(),
+    )
+    .await
+}
+
+/// Dry run `pt` with the provisional budget of `policy`, and derive the budget from its cost
+pub async fn estimate_gas_budget_with_policy(
+    rpc_client: &impl NodeApi,
+    sender: SuiAddress,
+    pt: ProgrammableTransaction,
+    gas_payment: Vec<ObjectRef>,
+    policy: &GasBudgetPolicy,
+) -> anyhow::Result<GasBudgetEstimate> {
+    let gas_price = rpc_client.get_reference_gas_price().await?;
+    dry_run_estimate(
+        rpc_client,
+        sender,
+        pt,
+        gas_payment,
+        policy.provisional_budget,
+        gas_price,
+        policy,
+    )
+    .await
+}
+
+/// Estimate the budget of a PTB depending on the budget itself, as when splitting everything but
+/// the budget off the gas coin. `build` is called with the candidate budget, starting from the
+/// provisional one, until the estimate for the PTB it returns is the candidate itself.
+pub async fn estimate_gas_budget_until_stable(
+    rpc_client: &impl NodeApi,
+    sender: SuiAddress,
+    gas_payment: Vec<ObjectRef>,
+    policy: &GasBudgetPolicy,
+    mut build: impl FnMut(u64) -> anyhow::Result<ProgrammableTransaction>,
+) -> anyhow::Result<GasBudgetEstimate> {
+    let gas_price = rpc_client.get_reference_gas_price().await?;
+
+    let mut candidate = policy.provisional_budget;
+    for _ in 0..MAX_STABILIZATION_ROUNDS {
+        let estimate = dry_run_estimate(
+            rpc_client,
+            sender,
+            build(candidate)?,
+            gas_payment.clone(),
+            candidate,
+            gas_price,
+            policy,
+        )
+        .await?;
+        if estimate.budget == candidate {
+            return Ok(estimate);
+        }
+        candidate = estimate.budget;
+    }
+
+    bail!(
+        "Gas budget did not settle after {} dry runs, last candidate {}",
+        MAX_STABILIZATION_ROUNDS,
+        candidate
+    )
+}
+
+async fn dry_run_estimate(
+    rpc_client: &impl NodeApi,
+    sender: SuiAddress,
+    pt: ProgrammableTransaction,
+    gas_payment: Vec<ObjectRef>,
+    gas_budget: u64,
+    gas_price: u64,
+    policy: &GasBudgetPolicy,
+) -> anyhow::Result<GasBudgetEstimate> {
+    ensure!(
+        gas_budget <= MAX_GAS_BUDGET,
+        "Gas budget {} exceeds the max of {}",
+        gas_budget,
+        MAX_GAS_BUDGET
+    );
+    let tx_data = TransactionData::new_programmable(sender, gas_payment, pt, gas_budget, gas_price);
+    let response = rpc_client.dry_run_transaction_block(tx_data).await?;
+
+    ensure!(
+        response.effects.status().is_ok(),
+        "Dry run with a budget of {} failed: {:?}",
+        gas_budget,
+        response.effects.status()
+    );
+    let gas_cost_summary = response.effects.gas_cost_summary().clone();
+
+    Ok(GasBudgetEstimate {
+        budget: policy.budget(&gas_cost_summary)?,
+        gas_cost_summary,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use sui_sdk::rpc_types::SuiExecutionStatus;
+    use sui_types::{
+        programmable_transaction_builder::ProgrammableTransactionBuilder,
+        transaction::TransactionDataAPI,
+    };
+
+    use super::*;
+    use crate::{fake::dry_run_response, FakeNode};
+
+    fn cost(computation_cost: u64, storage_cost: u64, storage_rebate: u64) -> GasCostSummary {
+        GasCostSummary {
+            computation_cost,
+            storage_cost,
+            storage_rebate,
+            non_refundable_storage_fee: 0,
+        }
+    }
+
+    fn policy(margin_percent: u64, min_margin: u64) -> GasBudgetPolicy {
+        GasBudgetPolicy {
+            provisional_budget: DEFAULT_GAS_BUDGET,
+            margin_percent,
+            min_margin,
+        }
+    }
+
+    #[test]
+    fn percent_margin_applies_above_the_min_margin() {
+        let budget = policy(20, 1_000).budget(&cost(60_000, 40_000, 0)).unwrap();
+        assert_eq!(budget, 120_000);
+    }
+
+    #[test]
+    fn min_margin_applies_to_small_costs() {
+        let budget = policy(20, 1_000).budget(&cost(3_000, 1_000, 0)).unwrap();
+        assert_eq!(budget, 5_000);
+    }
+
+    #[test]
+    fn storage_rebate_is_not_deducted() {
+        let budget = policy(0, 0).budget(&cost(1_000, 2_000, 2_500)).unwrap();
+        assert_eq!(budget, 3_000);
+    }
+
+    #[test]
+    fn budgets_above_the_max_are_rejected() {
+        assert_eq!(
+            policy(0, 0).budget(&cost(MAX_GAS_BUDGET, 0, 0)).unwrap(),
+            MAX_GAS_BUDGET
+        );
+        assert!(policy(0, 1).budget(&cost(MAX_GAS_BUDGET, 0, 0)).is_err());
+        assert!(policy(20, 0)
+            .budget(&cost(MAX_GAS_BUDGET / 2, 0, 0))
+            .is_ok());
+        assert!(policy(20, 0).budget(&cost(MAX_GAS_BUDGET, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn overflowing_margins_saturate_and_are_rejected() {
+        assert!(policy(u64::MAX, 0).budget(&cost(1_000, 0, 0)).is_err());
+        assert!(policy(20, 0).budget(&cost(u64::MAX, u64::MAX, 0)).is_err());
+        assert!(policy(0, u64::MAX).budget(&cost(1, 0, 0)).is_err());
+    }
+
+    fn pt() -> ProgrammableTransaction {
+        ProgrammableTransactionBuilder::new().finish()
+    }
+
+    fn budgets(node: &FakeNode) -> Vec<u64> {
+        node.dry_runs().iter().map(|tx| tx.gas_budget()).collect()
+    }
+
+    #[tokio::test]
+    async fn estimate_dry_runs_with_the_provisional_budget() {
+        let node = FakeNode::new().push_dry_run(Ok(dry_run_response(
+            SuiExecutionStatus::Success,
+            &cost(60_000, 40_000, 10_000),
+        )));
+
+        let estimate = estimate_gas_budget_with_policy(
+            &node,
+            SuiAddress::ZERO,
+            pt(),
+            vec![],
+            &policy(20, 1_000),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(estimate.budget, 120_000);
+        assert_eq!(estimate.net_cost(), 90_000);
+        assert_eq!(budgets(&node), vec![DEFAULT_GAS_BUDGET]);
+    }
+
+    #[tokio::test]
+    async fn failed_dry_runs_are_errors() {
+        let node = FakeNode::new().push_dry_run(Ok(dry_run_response(
+            SuiExecutionStatus::Failure {
+                error: "MoveAbort(MoveLocation { .. }, 0) in command 0".to_string(),
+            },
+            &cost(60_000, 0, 0),
+        )));
+
+        let estimate = estimate_gas_budget_with_policy(
+            &node,
+            SuiAddress::ZERO,
+            pt(),
+            vec![],
+            &policy(20, 1_000),
+        )
+        .await;
+
+        assert!(estimate.is_err());
+    }
+
+    #[tokio::test]
+    async fn stabilisation_settles_on_a_fixed_point() {
+        // The storage cost grows with the budget, as a PTB splitting `balance - budget` would
+        let node = FakeNode::new().with_dry_run_handler(|tx| {
+            let storage_cost = if tx.gas_budget() > 100_000 {
+                20_000
+            } else {
+                10_000
+            };
+            Ok(dry_run_response(
+                SuiExecutionStatus::Success,
+                &cost(40_000, storage_cost, 0),
+            ))
+        });
+        let built = RefCell::new(vec![]);
+
+        let estimate = estimate_gas_budget_until_stable(
+            &node,
+            SuiAddress::ZERO,
+            vec![],
+            &policy(50, 0),
+            |candidate| {
+                built.borrow_mut().push(candidate);
+                Ok(pt())
+            },
+        )
+        .await
+        .unwrap();
+
+        // 40_000 + 20_000 at the provisional budget, then 40_000 + 10_000 from there on
+        assert_eq!(estimate.budget, 75_000);
+        assert_eq!(budgets(&node), vec![DEFAULT_GAS_BUDGET, 90_000, 75_000]);
+        assert_eq!(*built.borrow(), budgets(&node));
+    }
+
+    #[tokio::test]
+    async fn stabilisation_gives_up_on_oscillating_budgets() {
+        // The cost alternates between two values, so the estimate never matches the candidate
+        let node = FakeNode::new().with_dry_run_handler(|tx| {
+            let computation_cost = if tx.gas_budget() > 100_000 {
+                50_000
+            } else {
+                150_000
+            };
+            Ok(dry_run_response(
+                SuiExecutionStatus::Success,
+                &cost(computation_cost, 0, 0),
+            ))
+        });
+
+        let estimate = estimate_gas_budget_until_stable(
+            &node,
+            SuiAddress::ZERO,
+            vec![],
+            &policy(0, 0),
+            |_| Ok(pt()),
+        )
+        .await;
+
+        assert!(estimate.is_err());
+        assert_eq!(budgets(&node).len(), MAX_STABILIZATION_ROUNDS);
+    }
+
+    #[tokio::test]
+    async fn stabilisation_stops_on_build_errors() {
+        let node = FakeNode::new();
+
+        let estimate = estimate_gas_budget_until_stable(
+            &node,
+            SuiAddress::ZERO,
+            vec![],
+            &policy(20, 0),
+            |candidate| bail!("Cannot split off {}", candidate),
+        )
+        .await;
+
+        assert!(estimate.is_err());
+        assert!(node.dry_runs().is_empty());
+    }
+}
@@ -0,0 +1,157 @@
+use futures::TryStreamExt;
+use sui_sdk::rpc_types::{
+    SuiObjectData, SuiObjectDataFilter, SuiObjectDataOptions, SuiObjectResponseQuery, SuiRawData,
+};
+use sui_types::{base_types::SuiAddress, coin::Coin, TypeTag};
+
+use crate::{owned_objects_stream, DecodeMode, Error, Fetched, NodeApi, Result};
+
+/// An owned `Coin<T>` object along with its decoded balance
+#[derive(Debug, Clone)]
+pub struct OwnedCoin {
+    pub object: SuiObjectData,
+    pub coin_type: TypeTag,
+    pub balance: u64,
+}
+
+impl OwnedCoin {
+    /// Decode the balance of a `Coin<coin_type>` from the object's raw BCS content
+    pub fn try_from_object(object: SuiObjectData, coin_type: &TypeTag) -> Result<Self> {
+        let Some(SuiRawData::MoveObject(raw_object)) = &object.bcs else {
+            return Err(Error::MissingContent(object.object_id));
+        };
+        let coin = Coin::from_bcs_bytes(&raw_object.bcs_bytes)
+            .map_err(|e| Error::decode(object.object_id, e))?;
+
+        Ok(Self {
+            balance: coin.value(),
+            coin_type: coin_type.clone(),
+            object,
+        })
+    }
+}
+
+/// Fetch all non-empty `Coin<coin_type>` objects owned by `owner`, sorted by decreasing balance.
+pub async fn fetch_sorted_coins(
+    rpc_client: &impl NodeApi,
+    owner: &SuiAddress,
+    coin_type: TypeTag,
+) -> Result<Vec<OwnedCoin>> {
+    let fetched =
+        fetch_sorted_coins_with_mode(rpc_client, owner, coin_type, DecodeMode::Strict).await?;
+    Ok(fetched.items)
+}
+
+/// Same as `fetch_sorted_coins`, but in `DecodeMode::Skip` objects that fail to decode are
+/// collected in `Fetched::failures` instead of failing the whole call.
+pub async fn fetch_sorted_coins_with_mode(
+    rpc_client: &impl NodeApi,
+    owner: &SuiAddress,
+    coin_type: TypeTag,
+    mode: DecodeMode,
+) -> Result<Fetched<OwnedCoin>> {
+    let query = SuiObjectResponseQuery {
+        filter: Some(SuiObjectDataFilter::MatchAll(vec![
+            SuiObjectDataFilter::StructType(Coin::type_(coin_type.clone())),
+        ])),
+        options: Some(SuiObjectDataOptions::full_content()),
+    };
+
+    let mut fetched = Fetched::default();
+    let mut objects = Box::pin(owned_objects_stream(rpc_client, *owner, query, None));
+    while let Some(object) = objects.try_next().await? {
+        match OwnedCoin::try_from_object(object, &coin_type) {
+            Ok(coin) if coin.balance > 0 => fetched.items.push(coin),
+            Ok(_) => {}
+            Err(e) if mode == DecodeMode::Skip => fetched.failures.push(e),
+            Err(e) => return Err(e),
+        }
+    }
+
+    fetched.items.sort_by(|a, b| b.balance.cmp(&a.balance));
+
+    Ok(fetched)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use sui_types::{base_types::ObjectID, gas_coin::GAS};
+
+    use super::*;
+    use crate::{
+        fake::{coin_object, object_id},
+        FakeNode,
+    };
+
+    const OWNER: SuiAddress = SuiAddress::ZERO;
+
+    fn usdc() -> TypeTag {
+        TypeTag::from_str("0xabc::usdc::USDC").unwrap()
+    }
+
+    fn balances(coins: &[OwnedCoin]) -> Vec<(ObjectID, u64)> {
+        coins
+            .iter()
+            .map(|coin| (coin.object.object_id, coin.balance))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn coins_of_the_type_are_fetched_sorted_by_balance() {
+        let node = FakeNode::new().with_page_size(2).with_owned_objects(
+            OWNER,
+            vec![
+                coin_object(object_id(1), usdc(), 20),
+                coin_object(object_id(2), GAS::type_tag(), 100),
+                coin_object(object_id(3), usdc(), 0),
+                coin_object(object_id(4), usdc(), 50),
+                coin_object(object_id(5), usdc(), 5),
+            ],
+        );
+
+        let coins = fetch_sorted_coins(&node, &OWNER, usdc()).await.unwrap();
+        assert_eq!(
+            balances(&coins),
+            vec![(object_id(4), 50), (object_id(1), 20), (object_id(5), 5)]
+        );
+        assert!(coins.iter().all(|coin| coin.coin_type == usdc()));
+    }
+
+    #[tokio::test]
+    async fn undecodable_coins_are_skipped_on_request() {
+        let mut truncated = coin_object(object_id(2), usdc(), 10);
+        // Cut within the balance, after the 32 bytes of the ID
+        if let Some(SuiRawData::MoveObject(raw_object)) = &mut truncated.bcs {
+            raw_object.bcs_bytes.truncate(36);
+        }
+        let mut without_content = coin_object(object_id(3), usdc(), 10);
+        without_content.bcs = None;
+        let node = FakeNode::new().with_owned_objects(
+            OWNER,
+            vec![
+                coin_object(object_id(1), usdc(), 30),
+                truncated,
+                without_content,
+                coin_object(object_id(4), usdc(), 40),
+            ],
+        );
+
+        let result = fetch_sorted_coins(&node, &OWNER, usdc()).await;
+        assert!(matches!(result, Err(Error::Decode { object_id: id, .. }) if id == object_id(2)));
+
+        let fetched = fetch_sorted_coins_with_mode(&node, &OWNER, usdc(), DecodeMode::Skip)
+            .await
+            .unwrap();
+        assert_eq!(
+            balances(&fetched.items),
+            vec![(object_id(4), 40), (object_id(1), 30)]
+        );
+        assert!(matches!(
+            fetched.failures.as_slice(),
+            [Error::Decode { object_id: decode_id, .. }, Error::MissingContent(missing_id)]
+                if *decode_id == object_id(2) && *missing_id == object_id(3)
+        ));
+    }
+}
@@ -0,0 +1,372 @@
+use std::{fmt, path::PathBuf, str::FromStr};
+
+use anyhow::{anyhow, bail, ensure, Context};
+use clap::Parser;
+use serde::Deserialize;
+use sui_types::base_types::SuiAddress;
+
+use crate::NodeApi;
+
+/// Gas budget used when none is configured, 0.1 Sui
+pub const DEFAULT_GAS_BUDGET: u64 = 100_000_000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Devnet,
+    Localnet,
+    /// Any other fullnode, by URL
+    Custom(String),
+}
+
+impl Network {
+    pub fn rpc_url(&self) -> &str {
+        match self {
+            Network::Mainnet => "https://fullnode.mainnet.sui.io:443",
+            Network::Testnet => "https://fullnode.testnet.sui.io:443",
+            Network::Devnet => "https://fullnode.devnet.sui.io:443",
+            Network::Localnet => "http://127.0.0.1:9000",
+            Network::Custom(url) => url,
+        }
+    }
+
+    /// Chain identifier (digest of the genesis checkpoint) of networks that are never reset
+    pub fn chain_identifier(&self) -> Option<&str> {
+        match self {
+            Network::Mainnet => Some("35834a8a"),
+            Network::Testnet => Some("4c78adac"),
+            Network::Devnet | Network::Localnet | Network::Custom(_) => None,
+        }
+    }
+}
+
+impl FromStr for Network {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            "devnet" => Ok(Network::Devnet),
+            "localnet" => Ok(Network::Localnet),
+            url if url.starts_with("http://") || url.starts_with("https://") => {
+                Ok(Network::Custom(url.to_string()))
+            }
+            _ => Err(anyhow!(
+                "Unknown network {:?}, expected mainnet, testnet, devnet, localnet or a URL",
+                s
+            )),
+        }
+    }
+}
+
+impl TryFrom<String> for Network {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> anyhow::Result<Self> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Network::Mainnet => write!(f, "mainnet"),
+            Network::Testnet => write!(f, "testnet"),
+            Network::Devnet => write!(f, "devnet"),
+            Network::Localnet => write!(f, "localnet"),
+            Network::Custom(url) => write!(f, "{}", url),
+        }
+    }
+}
+
+/// Command line flags, each one falling back to an environment variable
+#[derive(Debug, Parser)]
+struct Cli {
+    /// TOML file with any of `network`, `sender`, `gas_budget`, `keystore`, `chain_identifier` and
+    /// `find_min_budget`
+    #[arg(long, env = "SUI_COOKBOOK_CONFIG")]
+    config: Option<PathBuf>,
+    /// mainnet, testnet, devnet, localnet or a fullnode URL
+    #[arg(long, env = "SUI_NETWORK")]
+    network: Option<Network>,
+    #[arg(long, env = "SUI_SENDER")]
+    sender: Option<SuiAddress>,
+    #[arg(long, env = "SUI_GAS_BUDGET")]
+    gas_budget: Option<u64>,
+    #[arg(long, env = "SUI_KEYSTORE")]
+    keystore: Option<PathBuf>,
+    /// Expected chain identifier of the node. Devnet, localnet and URLs have none by default, but
+    /// must not run mainnet or testnet
+    #[arg(long, env = "SUI_CHAIN_IDENTIFIER")]
+    chain_identifier: Option<String>,
+    /// Also binary search the smallest gas budget, at the cost of ~20 more dry runs
+    #[arg(long, env = "SUI_FIND_MIN_BUDGET")]
+    find_min_budget: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FileConfig {
+    network: Option<Network>,
+    sender: Option<SuiAddress>,
+    gas_budget: Option<u64>,
+    keystore: Option<PathBuf>,
+    chain_identifier: Option<String>,
+    find_min_budget: Option<bool>,
+}
+
+/// Settings shared by all examples, read from CLI flags, then environment variables, then the
+/// TOML file given by `--config`, then defaults.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub network: Network,
+    pub sender: SuiAddress,
+    pub gas_budget: u64,
+    pub keystore: PathBuf,
+    /// Overrides `Network::chain_identifier`
+    pub chain_identifier: Option<String>,
+    /// Whether examples search for the smallest gas budget, off by default
+    pub find_min_budget: bool,
+}
+
+impl Config {
+    /// Load the configuration of an example, `default_sender` being used when no sender is
+    /// configured. The network defaults to mainnet.
+    pub fn load(default_sender: SuiAddress) -> anyhow::Result<Self> {
+        Self::from_cli(Cli::parse(), default_sender)
+    }
+
+    fn from_cli(cli: Cli, default_sender: SuiAddress) -> anyhow::Result<Self> {
+        let file = match &cli.config {
+            Some(path) => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read config {}", path.display()))?;
+                toml::from_str(&content)
+                    .with_context(|| format!("Invalid config {}", path.display()))?
+            }
+            None => FileConfig::default(),
+        };
+
+        let keystore = match cli.keystore.or(file.keystore) {
+            Some(keystore) => keystore,
+            None => default_keystore_path()?,
+        };
+
+        Ok(Self {
+            network: cli.network.or(file.network).unwrap_or(Network::Mainnet),
+            sender: cli.sender.or(file.sender).unwrap_or(default_sender),
+            gas_budget: cli
+                .gas_budget
+                .or(file.gas_budget)
+                .unwrap_or(DEFAULT_GAS_BUDGET),
+            keystore,
+            chain_identifier: cli.chain_identifier.or(file.chain_identifier),
+            find_min_budget: cli.find_min_budget || file.find_min_budget.unwrap_or(false),
+        })
+    }
+
+    pub fn rpc_url(&self) -> &str {
+        self.network.rpc_url()
+    }
+
+    /// Make sure the connected node runs the configured network, so that e.g. a testnet config
+    /// can never end up talking to mainnet. Networks without a known chain identifier, which get
+    /// reset, are only checked not to be mainnet or testnet.
+    pub async fn verify_chain_identifier(&self, rpc_client: &impl NodeApi) -> anyhow::Result<()> {
+        let actual = rpc_client.get_chain_identifier().await?;
+
+        let expected = self
+            .chain_identifier
+            .as_deref()
+            .or(self.network.chain_identifier());
+        match expected {
+            Some(expected) => ensure!(
+                actual == expected,
+                "Node at {} runs chain {}, expected {} for {}",
+                self.rpc_url(),
+                actual,
+                expected,
+                self.network
+            ),
+            None => {
+                let persistent_networks = [Network::Mainnet, Network::Testnet];
+                if let Some(network) = persistent_networks
+                    .iter()
+                    .find(|network| network.chain_identifier() == Some(actual.as_str()))
+                {
+                    bail!(
+                        "Node at {} runs {} (chain {}), expected {}",
+                        self.rpc_url(),
+                        network,
+                        actual,
+                        self.network
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn default_keystore_path() -> anyhow::Result<PathBuf> {
+    let home = std::env::var("HOME").context("HOME is not set, configure a keystore path")?;
+    Ok(PathBuf::from(home).join(".sui/sui_config/sui.keystore"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FakeNode;
+
+    const ENV_VARS: [&str; 7] = [
+        "SUI_COOKBOOK_CONFIG",
+        "SUI_NETWORK",
+        "SUI_SENDER",
+        "SUI_GAS_BUDGET",
+        "SUI_KEYSTORE",
+        "SUI_CHAIN_IDENTIFIER",
+        "SUI_FIND_MIN_BUDGET",
+    ];
+
+    fn config_for(network: Network, chain_identifier: Option<&str>) -> Config {
+        Config {
+            network,
+            sender: SuiAddress::ZERO,
+            gas_budget: DEFAULT_GAS_BUDGET,
+            keystore: PathBuf::new(),
+            chain_identifier: chain_identifier.map(str::to_string),
+            find_min_budget: false,
+        }
+    }
+
+    #[test]
+    fn networks_parse_from_names_and_urls() {
+        for (name, network) in [
+            ("mainnet", Network::Mainnet),
+            ("testnet", Network::Testnet),
+            ("devnet", Network::Devnet),
+            ("localnet", Network::Localnet),
+            (
+                "http://127.0.0.1:9100",
+                Network::Custom("http://127.0.0.1:9100".to_string()),
+            ),
+            (
+                "https://rpc.example",
+                Network::Custom("https://rpc.example".to_string()),
+            ),
+        ] {
+            assert_eq!(Network::from_str(name).unwrap(), network);
+            assert_eq!(network.to_string(), name);
+        }
+
+        assert!(Network::from_str("Mainnet").is_err());
+        assert!(Network::from_str("127.0.0.1:9000").is_err());
+        assert!(Network::from_str("").is_err());
+    }
+
+    // Single test, as the environment is shared by the whole test binary
+    #[test]
+    fn flags_override_environment_overriding_file() {
+        for var in ENV_VARS {
+            std::env::remove_var(var);
+        }
+        let sender = SuiAddress::from_str(
+            "0x0000000000000000000000000000000000000000000000000000000000000abc",
+        )
+        .unwrap();
+        let path = std::env::temp_dir().join(format!("sui-config-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            format!(
+                "network = \"testnet\"\n\
+                 sender = \"{}\"\n\
+                 gas_budget = 1\n\
+                 keystore = \"/file.keystore\"\n\
+                 find_min_budget = true\n",
+                sender
+            ),
+        )
+        .unwrap();
+
+        std::env::set_var("SUI_COOKBOOK_CONFIG", &path);
+        std::env::set_var("SUI_NETWORK", "devnet");
+        std::env::set_var("SUI_GAS_BUDGET", "2");
+        let cli = Cli::try_parse_from(["example", "--gas-budget", "3"]).unwrap();
+        let config = Config::from_cli(cli, SuiAddress::ZERO).unwrap();
+
+        assert_eq!(config.gas_budget, 3);
+        assert_eq!(config.network, Network::Devnet);
+        assert_eq!(config.sender, sender);
+        assert_eq!(config.keystore, PathBuf::from("/file.keystore"));
+        assert!(config.find_min_budget);
+        assert_eq!(config.chain_identifier, None);
+
+        // Defaults, without the file
+        std::env::remove_var("SUI_COOKBOOK_CONFIG");
+        let cli = Cli::try_parse_from(["example"]).unwrap();
+        let config = Config::from_cli(cli, sender).unwrap();
+        assert_eq!(config.gas_budget, 2);
+        assert_eq!(config.sender, sender);
+        assert!(!config.find_min_budget);
+
+        std::env::remove_var("SUI_NETWORK");
+        std::env::remove_var("SUI_GAS_BUDGET");
+        let cli = Cli::try_parse_from(["example"]).unwrap();
+        let config = Config::from_cli(cli, sender).unwrap();
+        assert_eq!(config.network, Network::Mainnet);
+        assert_eq!(config.gas_budget, DEFAULT_GAS_BUDGET);
+
+        std::fs::write(&path, "gas_budjet = 1\n").unwrap();
+        let cli = Cli::try_parse_from(["example", "--config", path.to_str().unwrap()]).unwrap();
+        assert!(Config::from_cli(cli, sender).is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn known_networks_must_match_their_chain_identifier() {
+        let mainnet = FakeNode::new().with_chain_identifier("35834a8a");
+        let testnet = FakeNode::new().with_chain_identifier("4c78adac");
+
+        let config = config_for(Network::Mainnet, None);
+        assert!(config.verify_chain_identifier(&mainnet).await.is_ok());
+        assert!(config.verify_chain_identifier(&testnet).await.is_err());
+
+        let config = config_for(Network::Testnet, None);
+        assert!(config.verify_chain_identifier(&testnet).await.is_ok());
+        assert!(config.verify_chain_identifier(&mainnet).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn configured_chain_identifiers_must_match() {
+        let node = FakeNode::new().with_chain_identifier("deadbeef");
+
+        let config = config_for(Network::Localnet, Some("deadbeef"));
+        assert!(config.verify_chain_identifier(&node).await.is_ok());
+
+        let config = config_for(Network::Devnet, Some("0badf00d"));
+        assert!(config.verify_chain_identifier(&node).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reset_networks_must_not_be_mainnet_or_testnet() {
+        let networks = [
+            Network::Devnet,
+            Network::Localnet,
+            Network::Custom("http://127.0.0.1:9100".to_string()),
+        ];
+        for network in networks {
+            let config = config_for(network, None);
+            for chain_identifier in ["35834a8a", "4c78adac"] {
+                let node = FakeNode::new().with_chain_identifier(chain_identifier);
+                assert!(config.verify_chain_identifier(&node).await.is_err());
+            }
+            let node = FakeNode::new().with_chain_identifier("deadbeef");
+            assert!(config.verify_chain_identifier(&node).await.is_ok());
+        }
+    }
+}
@@ -0,0 +1,191 @@
+//! Mermaid flowcharts of the data flow of a PTB, in the style of the example READMEs.
+
+use std::fmt::Write;
+
+use move_core_types::account_address::AccountAddress;
+use sui_types::transaction::{Argument, Command, ProgrammableTransaction};
+
+use crate::labels::{command_arguments, short_type, Label, Labels};
+
+/// Render `pt` as a mermaid flowchart: one node per command, an edge from the entry inputs or the
+/// producing command for each argument, and a dotted edge between consecutive commands not
+/// already linked, so that the execution order stays visible.
+pub fn to_mermaid(pt: &ProgrammableTransaction, labels: &Labels) -> String {
+    let mut out = String::new();
+    let mut edges: Vec<String> = vec![];
+    let mut linked: Vec<(usize, usize)> = vec![];
+
+    writeln!(out, "flowchart TD").unwrap();
+    writeln!(out, "  ENTRY[[Entry inputs]]").unwrap();
+
+    for (index, command) in pt.commands.iter().enumerate() {
+        writeln!(out, "  {}({}. {})", index, index, command_name(command)).unwrap();
+
+        for argument in command_arguments(command) {
+            let source = match argument {
+                Argument::GasCoin | Argument::Input(_) => "ENTRY".to_string(),
+                Argument::Result(command) | Argument::NestedResult(command, _) => {
+                    linked.push((command as usize, index));
+                    command.to_string()
+                }
+            };
+            let edge = format!(
+                "  {} -->|\"{}\"| {}",
+                source,
+                escape(&edge_label(labels, argument)),
+                index
+            );
+            if !edges.contains(&edge) {
+                edges.push(edge);
+            }
+        }
+
+        if index > 0 && !linked.contains(&(index - 1, index)) {
+            edges.push(format!("  {} -.- {}", index - 1, index));
+        }
+    }
+
+    writeln!(out).unwrap();
+    for edge in edges {
+        writeln!(out, "{}", edge).unwrap();
+    }
+
+    out
+}
+
+fn edge_label(labels: &Labels, argument: Argument) -> String {
+    match labels.get(argument) {
+        Some(Label {
+            name,
+            type_tag: Some(type_tag),
+        }) => format!("{} - {}", name, short_type(type_tag)),
+        Some(Label { name, .. }) => name.clone(),
+        None => match argument {
+            Argument::GasCoin => "GasCoin".to_string(),
+            Argument::Input(input) => format!("input {}", input),
+            Argument::Result(command) => format!("result {}", command),
+            Argument::NestedResult(command, index) => format!("result {}.{}", command, index),
+        },
+    }
+}
+
+fn command_name(command: &Command) -> String {
+    match command {
+        Command::MoveCall(call) => format!(
+            "0x{}::{}::{}",
+            AccountAddress::from(call.package).short_str_lossless(),
+            call.module,
+            call.function
+        ),
+        Command::TransferObjects(..) => "native TransferObjects".to_string(),
+        Command::SplitCoins(..) => "native SplitCoins".to_string(),
+        Command::MergeCoins(..) => "native MergeCoins".to_string(),
+        Command::Publish(..) => "native Publish".to_string(),
+        Command::MakeMoveVec(..) => "native MakeMoveVec".to_string(),
+        Command::Upgrade(..) => "native Upgrade".to_string(),
+    }
+}
+
+/// Mermaid entity codes for the characters breaking a quoted label
+fn escape(label: &str) -> String {
+    label
+        .replace('"', "#quot;")
+        .replace('<', "#lt;")
+        .replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_types::{
+        base_types::{ObjectDigest, ObjectID, SequenceNumber, SuiAddress},
+        transaction::{CallArg, ObjectArg, ProgrammableMoveCall},
+        Identifier, SUI_FRAMEWORK_PACKAGE_ID,
+    };
+
+    use super::*;
+    use crate::{
+        fake::object_id,
+        framework::{Coin, SUI},
+        Res,
+    };
+
+    fn move_call(
+        package: ObjectID,
+        module: &str,
+        function: &str,
+        arguments: Vec<Argument>,
+    ) -> Command {
+        Command::MoveCall(Box::new(ProgrammableMoveCall {
+            package,
+            module: Identifier::new(module).unwrap(),
+            function: Identifier::new(function).unwrap(),
+            type_arguments: vec![],
+            arguments,
+        }))
+    }
+
+    #[test]
+    fn flowchart_matches_the_snapshot() {
+        // Split a coin in 2, merge the second half back, then send the first half away after an
+        // unrelated call
+        let pt = ProgrammableTransaction {
+            inputs: vec![
+                CallArg::Object(ObjectArg::ImmOrOwnedObject((
+                    object_id(1),
+                    SequenceNumber::from_u64(1),
+                    ObjectDigest::new([0; 32]),
+                ))),
+                CallArg::Pure(bcs::to_bytes(&1_000u64).unwrap()),
+                CallArg::Pure(bcs::to_bytes(&SuiAddress::ZERO).unwrap()),
+            ],
+            commands: vec![
+                Command::SplitCoins(
+                    Argument::Input(0),
+                    vec![Argument::Input(1), Argument::Input(1)],
+                ),
+                move_call(
+                    SUI_FRAMEWORK_PACKAGE_ID,
+                    "coin",
+                    "join",
+                    vec![Argument::Input(0), Argument::NestedResult(0, 1)],
+                ),
+                move_call(
+                    ObjectID::from_hex_literal("0xabc").unwrap(),
+                    "pool",
+                    "touch",
+                    vec![],
+                ),
+                Command::TransferObjects(vec![Argument::NestedResult(0, 0)], Argument::Input(2)),
+            ],
+        };
+        let mut labels = Labels::new();
+        labels
+            .name(Res::<Coin<SUI>>::unchecked(Argument::Input(0)), "coin")
+            .name(
+                Res::<Coin<SUI>>::unchecked(Argument::NestedResult(0, 0)),
+                "first half",
+            )
+            .name(
+                Res::<SuiAddress>::unchecked(Argument::Input(2)),
+                "recipient",
+            );
+
+        let expected = r#"flowchart TD
+  ENTRY[[Entry inputs]]
+  0(0. native SplitCoins)
+  1(1. 0x2::coin::join)
+  2(2. 0xabc::pool::touch)
+  3(3. native TransferObjects)
+
+  ENTRY -->|"coin - Coin#lt;SUI#gt;"| 0
+  ENTRY -->|"input 1"| 0
+  ENTRY -->|"coin - Coin#lt;SUI#gt;"| 1
+  0 -->|"result 0.1"| 1
+  1 -.- 2
+  0 -->|"first half - Coin#lt;SUI#gt;"| 3
+  ENTRY -->|"recipient - address"| 3
+  2 -.- 3
+"#;
+        assert_eq!(to_mermaid(&pt, &labels), expected);
+    }
+}
@@ -0,0 +1,544 @@
+//! Readable pseudo-code of a PTB, for logs. Inputs read `$i`, command results `%i` or `%i.j` and
+//! the coin paying for gas `gas`, unless given a name through `Labels`:
+//!
+//! ```text
+//! $0 kiosk_id: ID = 0x5ee3..
+//! %6 (%6.0 kiosk, %6.1 kiosk_owner_cap) = 0x2::kiosk::new()
+//! %8 = 0x2::kiosk::close_and_withdraw(%kiosk, %kiosk_owner_cap)
+//! ```
+
+use std::{collections::HashMap, fmt::Write};
+
+use move_core_types::account_address::AccountAddress;
+use sui_types::{
+    base_types::SuiAddress,
+    transaction::{Argument, CallArg, Command, ObjectArg, ProgrammableTransaction},
+    TypeTag,
+};
+
+use crate::labels::{short_type, Labels};
+
+/// Render `pt` as one line per input and per command. Pure inputs are decoded when their type is
+/// known, either from `labels` or from the native command using them.
+pub fn disassemble(pt: &ProgrammableTransaction, labels: &Labels) -> String {
+    let mut out = String::new();
+    let input_types = input_types(pt, labels);
+
+    for (index, input) in pt.inputs.iter().enumerate() {
+        let argument = Argument::Input(index as u16);
+        let type_tag = input_types.get(&(index as u16));
+
+        let mut line = format!("${}", index);
+        if let Some(label) = labels.get(argument) {
+            write!(line, " {}", identifier(&label.name)).unwrap();
+        }
+        if let Some(type_tag) = type_tag {
+            write!(line, ": {}", short_type(type_tag)).unwrap();
+        }
+        let value = match input {
+            CallArg::Pure(bytes) => type_tag
+                .and_then(|type_tag| decode_pure(bytes, type_tag))
+                .unwrap_or_else(|| format!("pure 0x{}", hex(bytes))),
+            CallArg::Object(ObjectArg::ImmOrOwnedObject((id, version, _))) => {
+                format!("object {}@{}", id, version.value())
+            }
+            CallArg::Object(ObjectArg::SharedObject {
+                id,
+                initial_shared_version,
+                mutable,
+            }) => format!(
+                "shared {}{}@{}",
+                if *mutable { "mut " } else { "" },
+                id,
+                initial_shared_version.value()
+            ),
+        };
+        writeln!(out, "{} = {}", line, value).unwrap();
+    }
+
+    for (index, command) in pt.commands.iter().enumerate() {
+        let mut line = format!("%{}", index);
+        let nested = nested_names(labels, index as u16, command);
+        if nested.len() > 1 {
+            write!(line, " ({})", nested.join(", ")).unwrap();
+        } else if let Some(label) = labels.get(Argument::Result(index as u16)) {
+            write!(line, " {}", identifier(&label.name)).unwrap();
+        }
+        writeln!(out, "{} = {}", line, command_text(command, labels)).unwrap();
+    }
+
+    out
+}
+
+/// Names of the results of a tuple-returning command, by position
+fn nested_names(labels: &Labels, command: u16, pt_command: &Command) -> Vec<String> {
+    let count = match pt_command {
+        Command::SplitCoins(_, amounts) => amounts.len(),
+        // The arity of Move calls is unknown here, labels past the first gap are not shown
+        _ => 8,
+    };
+    (0..count as u16)
+        .map_while(|index| {
+            labels
+                .get(Argument::NestedResult(command, index))
+                .map(|label| format!("%{}.{} {}", command, index, identifier(&label.name)))
+        })
+        .collect()
+}
+
+fn command_text(command: &Command, labels: &Labels) -> String {
+    let argument = |argument: &Argument| argument_text(*argument, labels);
+    let list = |arguments: &[Argument]| {
+        arguments
+            .iter()
+            .map(argument)
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match command {
+        Command::MoveCall(call) => {
+            let type_arguments = if call.type_arguments.is_empty() {
+                String::new()
+            } else {
+                format!(
+                    "<{}>",
+                    call.type_arguments
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            };
+            format!(
+                "0x{}::{}::{}{}({})",
+                AccountAddress::from(call.package).short_str_lossless(),
+                call.module,
+                call.function,
+                type_arguments,
+                list(&call.arguments)
+            )
+        }
+        Command::TransferObjects(objects, recipient) => {
+            format!(
+                "TransferObjects([{}], {})",
+                list(objects),
+                argument(recipient)
+            )
+        }
+        Command::SplitCoins(coin, amounts) => {
+            format!("SplitCoins({}, [{}])", argument(coin), list(amounts))
+        }
+        Command::MergeCoins(destination, sources) => {
+            format!("MergeCoins({}, [{}])", argument(destination), list(sources))
+        }
+        Command::MakeMoveVec(type_tag, elements) => match type_tag {
+            Some(type_tag) => format!("MakeMoveVec<{}>([{}])", type_tag, list(elements)),
+            None => format!("MakeMoveVec([{}])", list(elements)),
+        },
+        Command::Publish(modules, dependencies) => format!(
+            "Publish({} modules, [{}])",
+            modules.len(),
+            dependencies
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Command::Upgrade(modules, dependencies, package, ticket) => format!(
+            "Upgrade({} modules, [{}], {}, {})",
+            modules.len(),
+            dependencies
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+            package,
+            argument(ticket)
+        ),
+    }
+}
+
+fn argument_text(argument: Argument, labels: &Labels) -> String {
+    let sigil = match argument {
+        Argument::GasCoin => "",
+        Argument::Input(_) => "$",
+        Argument::Result(_) | Argument::NestedResult(..) => "%",
+    };
+    if let Some(label) = labels.get(argument) {
+        return format!("{}{}", sigil, identifier(&label.name));
+    }
+    match argument {
+        Argument::GasCoin => "gas".to_string(),
+        Argument::Input(input) => format!("${}", input),
+        Argument::Result(command) => format!("%{}", command),
+        Argument::NestedResult(command, index) => format!("%{}.{}", command, index),
+    }
+}
+
+/// Types of the inputs, from the labels or else from the native commands they are passed to
+fn input_types(pt: &ProgrammableTransaction, labels: &Labels) -> HashMap<u16, TypeTag> {
+    let mut types = HashMap::new();
+    for command in &pt.commands {
+        match command {
+            Command::SplitCoins(_, amounts) => {
+                for amount in amounts {
+                    if let Argument::Input(input) = amount {
+                        types.insert(*input, TypeTag::U64);
+                    }
+                }
+            }
+            Command::TransferObjects(_, Argument::Input(input)) => {
+                types.insert(*input, TypeTag::Address);
+            }
+            _ => {}
+        }
+    }
+    for index in 0..pt.inputs.len() as u16 {
+        if let Some(type_tag) = labels
+            .get(Argument::Input(index))
+            .and_then(|label| label.type_tag.clone())
+        {
+            types.insert(index, type_tag);
+        }
+    }
+    types
+}
+
+/// Label names as identifiers, e.g. `original coin value / 2` as `original_coin_value_2`
+fn identifier(name: &str) -> String {
+    let identifier = name
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    if identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("_{}", identifier)
+    } else {
+        identifier
+    }
+}
+
+/// BCS-decode a pure input of type `type_tag`, if it is one of the types pure inputs can hold
+fn decode_pure(bytes: &[u8], type_tag: &TypeTag) -> Option<String> {
+    let mut reader = bytes;
+    let value = decode_value(&mut reader, type_tag)?;
+    reader.is_empty().then_some(value)
+}
+
+fn decode_value(reader: &mut &[u8], type_tag: &TypeTag) -> Option<String> {
+    Some(match type_tag {
+        TypeTag::Bool => match take(reader, 1)? {
+            [0] => "false".to_string(),
+            [1] => "true".to_string(),
+            _ => return None,
+        },
+        TypeTag::U8 => take(reader, 1)?[0].to_string(),
+        TypeTag::U16 => u16::from_le_bytes(take(reader, 2)?.try_into().ok()?).to_string(),
+        TypeTag::U32 => u32::from_le_bytes(take(reader, 4)?.try_into().ok()?).to_string(),
+        TypeTag::U64 => u64::from_le_bytes(take(reader, 8)?.try_into().ok()?).to_string(),
+        TypeTag::U128 => u128::from_le_bytes(take(reader, 16)?.try_into().ok()?).to_string(),
+        TypeTag::U256 => {
+            let mut bytes = take(reader, 32)?.to_vec();
+            bytes.reverse();
+            format!("0x{}", hex(&bytes))
+        }
+        TypeTag::Address => SuiAddress::from_bytes(take(reader, 32)?).ok()?.to_string(),
+        TypeTag::Vector(inner) if **inner == TypeTag::U8 => {
+            let length = uleb128(reader)?;
+            format!("0x{}", hex(take(reader, length)?))
+        }
+        TypeTag::Vector(inner) => {
+            let length = uleb128(reader)?;
+            let elements = (0..length)
+                .map(|_| decode_value(reader, inner))
+                .collect::<Option<Vec<_>>>()?;
+            format!("[{}]", elements.join(", "))
+        }
+        TypeTag::Struct(struct_tag) => {
+            let address = AccountAddress::short_str_lossless(&struct_tag.address);
+            match (
+                address.as_str(),
+                struct_tag.module.as_str(),
+                struct_tag.name.as_str(),
+                struct_tag.type_params.as_slice(),
+            ) {
+                ("1", "string", "String", []) | ("1", "ascii", "String", []) => {
+                    let length = uleb128(reader)?;
+                    format!("{:?}", std::str::from_utf8(take(reader, length)?).ok()?)
+                }
+                ("2", "object", "ID", []) => decode_value(reader, &TypeTag::Address)?,
+                ("1", "option", "Option", [inner]) => match uleb128(reader)? {
+                    0 => "none".to_string(),
+                    1 => format!("some({})", decode_value(reader, inner)?),
+                    _ => return None,
+                },
+                _ => return None,
+            }
+        }
+        TypeTag::Signer => return None,
+    })
+}
+
+fn take<'a>(reader: &mut &'a [u8], length: usize) -> Option<&'a [u8]> {
+    if reader.len() < length {
+        return None;
+    }
+    let (head, tail) = reader.split_at(length);
+    *reader = tail;
+    Some(head)
+}
+
+fn uleb128(reader: &mut &[u8]) -> Option<usize> {
+    let mut value = 0usize;
+    for shift in (0..32).step_by(7) {
+        let byte = take(reader, 1)?[0];
+        value |= ((byte & 0x7f) as usize) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use sui_types::base_types::{ObjectDigest, SequenceNumber};
+
+    use super::*;
+    use crate::{fake::object_id, Res};
+
+    fn type_tag(type_tag: &str) -> TypeTag {
+        TypeTag::from_str(type_tag).unwrap()
+    }
+
+    fn decode<T: serde::Serialize>(value: T, type_tag: &TypeTag) -> Option<String> {
+        decode_pure(&bcs::to_bytes(&value).unwrap(), type_tag)
+    }
+
+    /// Splits the gas coin in 2 with the same amount, merges the second half into an owned coin
+    /// and sends the first one away
+    fn pt() -> ProgrammableTransaction {
+        ProgrammableTransaction {
+            inputs: vec![
+                CallArg::Pure(bcs::to_bytes(&1_000u64).unwrap()),
+                CallArg::Pure(bcs::to_bytes(&SuiAddress::ZERO).unwrap()),
+                CallArg::Object(ObjectArg::SharedObject {
+                    id: object_id(7),
+                    initial_shared_version: SequenceNumber::from_u64(5),
+                    mutable: true,
+                }),
+                CallArg::Object(ObjectArg::ImmOrOwnedObject((
+                    object_id(8),
+                    SequenceNumber::from_u64(3),
+                    ObjectDigest::new([0; 32]),
+                ))),
+                CallArg::Pure(vec![0xff]),
+            ],
+            commands: vec![
+                Command::SplitCoins(
+                    Argument::GasCoin,
+                    vec![Argument::Input(0), Argument::Input(0)],
+                ),
+                Command::MergeCoins(Argument::Input(3), vec![Argument::NestedResult(0, 1)]),
+                Command::MakeMoveVec(Some(TypeTag::U8), vec![Argument::Input(4)]),
+                Command::TransferObjects(vec![Argument::NestedResult(0, 0)], Argument::Input(1)),
+            ],
+        }
+    }
+
+    #[test]
+    fn unlabelled_ptbs_use_positions() {
+        let expected = [
+            "$0: u64 = 1000".to_string(),
+            format!("$1: address = {}", SuiAddress::ZERO),
+            format!("$2 = shared mut {}@5", object_id(7)),
+            format!("$3 = object {}@3", object_id(8)),
+            "$4 = pure 0xff".to_string(),
+            "%0 = SplitCoins(gas, [$0, $0])".to_string(),
+            "%1 = MergeCoins($3, [%0.1])".to_string(),
+            "%2 = MakeMoveVec<u8>([$4])".to_string(),
+            "%3 = TransferObjects([%0.0], $1)".to_string(),
+        ];
+        assert_eq!(
+            disassemble(&pt(), &Labels::new()),
+            expected.join("\n") + "\n"
+        );
+    }
+
+    #[test]
+    fn labels_name_and_type_arguments() {
+        let mut labels = Labels::new();
+        labels
+            .name_argument(Argument::NestedResult(0, 0), "first coin")
+            .name_argument(Argument::NestedResult(0, 1), "second coin")
+            .name_argument(Argument::Input(3), "owned coin")
+            .name(Res::<bool>::unchecked(Argument::Input(4)), "flag");
+
+        let expected = [
+            "$0: u64 = 1000".to_string(),
+            format!("$1: address = {}", SuiAddress::ZERO),
+            format!("$2 = shared mut {}@5", object_id(7)),
+            format!("$3 owned_coin = object {}@3", object_id(8)),
+            // Not a valid bool, so shown raw despite its type
+            "$4 flag: bool = pure 0xff".to_string(),
+            "%0 (%0.0 first_coin, %0.1 second_coin) = SplitCoins(gas, [$0, $0])".to_string(),
+            "%1 = MergeCoins($owned_coin, [%second_coin])".to_string(),
+            "%2 = MakeMoveVec<u8>([$flag])".to_string(),
+            "%3 = TransferObjects([%first_coin], $1)".to_string(),
+        ];
+        assert_eq!(disassemble(&pt(), &labels), expected.join("\n") + "\n");
+    }
+
+    #[test]
+    fn label_names_become_identifiers() {
+        assert_eq!(identifier("new coin"), "new_coin");
+        assert_eq!(
+            identifier("original coin value / 2"),
+            "original_coin_value_2"
+        );
+        assert_eq!(identifier("  kiosk--owner cap "), "kiosk_owner_cap");
+        assert_eq!(identifier("2"), "_2");
+        assert_eq!(identifier("2nd coin"), "_2nd_coin");
+    }
+
+    #[test]
+    fn uleb128_reads_multi_byte_lengths() {
+        for (bytes, expected) in [
+            (&[0x00][..], 0),
+            (&[0x7f][..], 127),
+            (&[0x80, 0x01][..], 128),
+            (&[0xac, 0x02][..], 300),
+            (&[0xff, 0xff, 0xff, 0xff, 0x0f][..], u32::MAX as usize),
+        ] {
+            let mut reader = bytes;
+            assert_eq!(uleb128(&mut reader), Some(expected));
+            assert!(reader.is_empty());
+        }
+
+        // Stops after the last byte of the length
+        let mut reader = &[0x80, 0x01, 0x2a][..];
+        assert_eq!(uleb128(&mut reader), Some(128));
+        assert_eq!(reader, [0x2a]);
+
+        // Truncated, or longer than 32 bits
+        assert_eq!(uleb128(&mut &[0x80][..]), None);
+        assert_eq!(uleb128(&mut &[][..]), None);
+        assert_eq!(
+            uleb128(&mut &[0x80, 0x80, 0x80, 0x80, 0x80, 0x01][..]),
+            None
+        );
+    }
+
+    #[test]
+    fn pure_values_are_decoded() {
+        assert_eq!(decode(true, &TypeTag::Bool).unwrap(), "true");
+        assert_eq!(decode(7u8, &TypeTag::U8).unwrap(), "7");
+        assert_eq!(decode(500u16, &TypeTag::U16).unwrap(), "500");
+        assert_eq!(decode(70_000u32, &TypeTag::U32).unwrap(), "70000");
+        assert_eq!(
+            decode(u64::MAX, &TypeTag::U64).unwrap(),
+            u64::MAX.to_string()
+        );
+        assert_eq!(
+            decode(u128::MAX, &TypeTag::U128).unwrap(),
+            u128::MAX.to_string()
+        );
+
+        let mut u256 = [0u8; 32];
+        u256[0] = 1; // little-endian
+        assert_eq!(
+            decode(u256, &TypeTag::U256).unwrap(),
+            format!("0x{}01", "00".repeat(31))
+        );
+
+        let address = SuiAddress::from(object_id(3));
+        assert_eq!(
+            decode(address, &TypeTag::Address).unwrap(),
+            address.to_string()
+        );
+        assert_eq!(
+            decode(address, &type_tag("0x2::object::ID")).unwrap(),
+            address.to_string()
+        );
+    }
+
+    #[test]
+    fn pure_vectors_and_strings_are_decoded() {
+        let bytes = vec![0xab; 200]; // length over 127, on 2 bytes
+        assert_eq!(
+            decode(&bytes, &type_tag("vector<u8>")).unwrap(),
+            format!("0x{}", "ab".repeat(200))
+        );
+        assert_eq!(
+            decode(vec![1u64, 2, 3], &type_tag("vector<u64>")).unwrap(),
+            "[1, 2, 3]"
+        );
+        assert_eq!(
+            decode(vec![vec![true], vec![]], &type_tag("vector<vector<bool>>")).unwrap(),
+            "[[true], []]"
+        );
+
+        let long = "a".repeat(130);
+        assert_eq!(
+            decode(&long, &type_tag("0x1::string::String")).unwrap(),
+            format!("{:?}", long)
+        );
+        assert_eq!(
+            decode("say \"hi\"", &type_tag("0x1::ascii::String")).unwrap(),
+            r#""say \"hi\"""#
+        );
+        // Not UTF-8
+        assert_eq!(decode(vec![0xffu8], &type_tag("0x1::string::String")), None);
+    }
+
+    #[test]
+    fn pure_options_are_decoded() {
+        let option_u64 = type_tag("0x1::option::Option<u64>");
+        assert_eq!(decode(None::<u64>, &option_u64).unwrap(), "none");
+        assert_eq!(decode(Some(42u64), &option_u64).unwrap(), "some(42)");
+        assert_eq!(
+            decode(
+                Some("sui".to_string()),
+                &type_tag("0x1::option::Option<0x1::string::String>")
+            )
+            .unwrap(),
+            r#"some("sui")"#
+        );
+        // Options are vectors of at most 1 element
+        assert_eq!(decode(vec![1u64, 2], &option_u64), None);
+    }
+
+    #[test]
+    fn invalid_pure_values_are_not_decoded() {
+        // Trailing bytes
+        assert_eq!(decode_pure(&[1, 0], &TypeTag::U8), None);
+        assert_eq!(decode((7u64, 0u8), &TypeTag::U64), None);
+        // Missing bytes
+        assert_eq!(decode(7u32, &TypeTag::U64), None);
+        assert_eq!(decode_pure(&[3, 1, 2], &type_tag("vector<u8>")), None);
+        // Out of range
+        assert_eq!(decode(2u8, &TypeTag::Bool), None);
+        // Types pure inputs cannot hold
+        assert_eq!(decode_pure(&[], &TypeTag::Signer), None);
+        assert_eq!(
+            decode(7u64, &type_tag("0x2::coin::Coin<0x2::sui::SUI>")),
+            None
+        );
+    }
+
+    #[test]
+    fn decoding_a_value_leaves_the_following_bytes() {
+        let bytes = bcs::to_bytes(&(5u16, true)).unwrap();
+        let mut reader = &bytes[..];
+        assert_eq!(decode_value(&mut reader, &TypeTag::U16).unwrap(), "5");
+        assert_eq!(decode_value(&mut reader, &TypeTag::Bool).unwrap(), "true");
+        assert!(reader.is_empty());
+    }
+}
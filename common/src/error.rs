@@ -0,0 +1,59 @@
+use sui_types::base_types::{ObjectID, SuiAddress};
+
+use crate::limits::MAX_GAS_PAYMENT_OBJECTS;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("RPC request failed: {0}")]
+    Rpc(#[from] sui_sdk::error::Error),
+    #[error("Node error: {0}")]
+    Node(String),
+    #[error("Object {0} was returned without the requested content")]
+    MissingContent(ObjectID),
+    #[error("Failed to decode object {object_id}: {reason}")]
+    Decode { object_id: ObjectID, reason: String },
+    #[error("Insufficient gas balance: {available} available for a budget of {budget}")]
+    InsufficientGas { available: u128, budget: u64 },
+    #[error("No single coin covers a budget of {0}")]
+    NoSufficientCoin(u64),
+    #[error(
+        "Covering a budget of {budget} needs {count} gas objects, the protocol allows at most {max}",
+        max = MAX_GAS_PAYMENT_OBJECTS
+    )]
+    TooManyGasObjects { budget: u64, count: usize },
+    #[error("Gas budget {budget} plus amount {amount} overflows")]
+    GasTargetOverflow { budget: u64, amount: u64 },
+    #[error("Gas coins of {owner} do not cover a budget of {budget} plus an amount of {amount} ({pages_read} pages read)")]
+    GasTargetNotCovered {
+        owner: SuiAddress,
+        budget: u64,
+        amount: u64,
+        pages_read: usize,
+    },
+}
+
+impl Error {
+    pub fn decode(object_id: ObjectID, reason: impl ToString) -> Self {
+        Self::Decode {
+            object_id,
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// Whether objects failing to decode abort the whole fetch or get skipped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DecodeMode {
+    #[default]
+    Strict,
+    Skip,
+}
+
+/// Objects successfully decoded along with the per-object failures skipped in `DecodeMode::Skip`
+#[derive(Debug, Default)]
+pub struct Fetched<T> {
+    pub items: Vec<T>,
+    pub failures: Vec<Error>,
+}
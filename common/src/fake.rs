@@ -0,0 +1,761 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    sync::Mutex,
+};
+
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{json, Value};
+use sui_sdk::rpc_types::{
+    Coin, CoinPage, DevInspectResults, DryRunTransactionBlockResponse, ObjectsPage, Page,
+    SuiExecutionStatus, SuiObjectData, SuiObjectDataFilter, SuiObjectResponse,
+    SuiObjectResponseQuery, SuiRawData, SuiRawMoveObject, SuiTransactionBlockEffects,
+};
+use sui_types::{
+    base_types::{
+        ObjectDigest, ObjectID, ObjectRef, SequenceNumber, SuiAddress, TransactionDigest,
+    },
+    coin,
+    execution_status::ExecutionFailureStatus,
+    gas::GasCostSummary,
+    gas_coin::{GasCoin, GAS},
+    id::UID,
+    parse_sui_struct_tag,
+    transaction::{TransactionData, TransactionDataAPI, TransactionKind},
+    StructTag, TypeTag,
+};
+
+use crate::{Error, NodeApi, Result};
+
+type DryRunHandler =
+    Box<dyn Fn(&TransactionData) -> Result<DryRunTransactionBlockResponse> + Send + Sync>;
+type DevInspectHandler =
+    Box<dyn Fn(SuiAddress, &TransactionKind) -> Result<DevInspectResults> + Send + Sync>;
+
+/// In-memory `NodeApi` serving seeded objects and scripted responses, for running the cookbook
+/// offline.
+///
+/// Owned objects are filtered by type, package, module and ID as the node does, other filters are
+/// rejected. Data options are not applied: objects are returned with whatever data they were seeded
+/// with. Scripted dev-inspect and dry-run responses are served in order, falling back to the
+/// handler once the script runs out.
+pub struct FakeNode {
+    page_size: usize,
+    reference_gas_price: u64,
+    chain_identifier: String,
+    owned_objects: HashMap<SuiAddress, Vec<SuiObjectData>>,
+    coins: HashMap<SuiAddress, Vec<Coin>>,
+    dev_inspect_script: Mutex<VecDeque<Result<DevInspectResults>>>,
+    dry_run_script: Mutex<VecDeque<Result<DryRunTransactionBlockResponse>>>,
+    dev_inspect_handler: Option<DevInspectHandler>,
+    dry_run_handler: Option<DryRunHandler>,
+    dry_runs: Mutex<Vec<TransactionData>>,
+}
+
+impl Default for FakeNode {
+    fn default() -> Self {
+        Self {
+            page_size: 50, // fullnode default
+            reference_gas_price: 1_000,
+            chain_identifier: "fakenet".to_string(),
+            owned_objects: HashMap::new(),
+            coins: HashMap::new(),
+            dev_inspect_script: Mutex::new(VecDeque::new()),
+            dry_run_script: Mutex::new(VecDeque::new()),
+            dev_inspect_handler: None,
+            dry_run_handler: None,
+            dry_runs: Mutex::new(vec![]),
+        }
+    }
+}
+
+impl FakeNode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    pub fn with_reference_gas_price(mut self, reference_gas_price: u64) -> Self {
+        self.reference_gas_price = reference_gas_price;
+        self
+    }
+
+    pub fn with_chain_identifier(mut self, chain_identifier: impl Into<String>) -> Self {
+        self.chain_identifier = chain_identifier.into();
+        self
+    }
+
+    pub fn with_owned_objects(mut self, owner: SuiAddress, objects: Vec<SuiObjectData>) -> Self {
+        self.owned_objects.entry(owner).or_default().extend(objects);
+        self
+    }
+
+    /// Coins served by `get_coins`, independently from `with_owned_objects`
+    pub fn with_coins(mut self, owner: SuiAddress, coins: Vec<Coin>) -> Self {
+        self.coins.entry(owner).or_default().extend(coins);
+        self
+    }
+
+    pub fn push_dev_inspect(self, response: Result<DevInspectResults>) -> Self {
+        self.dev_inspect_script.lock().unwrap().push_back(response);
+        self
+    }
+
+    pub fn push_dry_run(self, response: Result<DryRunTransactionBlockResponse>) -> Self {
+        self.dry_run_script.lock().unwrap().push_back(response);
+        self
+    }
+
+    pub fn with_dev_inspect_handler(
+        mut self,
+        handler: impl Fn(SuiAddress, &TransactionKind) -> Result<DevInspectResults>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.dev_inspect_handler = Some(Box::new(handler));
+        self
+    }
+
+    pub fn with_dry_run_handler(
+        mut self,
+        handler: impl Fn(&TransactionData) -> Result<DryRunTransactionBlockResponse>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        self.dry_run_handler = Some(Box::new(handler));
+        self
+    }
+
+    /// Model the gas checks of the node through the dry run handler: transactions with a budget
+    /// below `min_gas_budget` fail with `InsufficientGas` as their execution would, the others
+    /// succeed costing `gas_cost_summary`
+    pub fn with_min_gas_budget(
+        self,
+        min_gas_budget: u64,
+        gas_cost_summary: GasCostSummary,
+    ) -> Self {
+        self.with_dry_run_handler(move |tx| {
+            if tx.gas_budget() < min_gas_budget {
+                // The whole budget is charged when running out of gas
+                let charged = GasCostSummary {
+                    computation_cost: tx.gas_budget(),
+                    storage_cost: 0,
+                    storage_rebate: 0,
+                    non_refundable_storage_fee: 0,
+                };
+                return Ok(dry_run_response(insufficient_gas_status(), &charged));
+            }
+            Ok(dry_run_response(
+                SuiExecutionStatus::Success,
+                &gas_cost_summary,
+            ))
+        })
+    }
+
+    /// Every transaction dry run so far, in order
+    pub fn dry_runs(&self) -> Vec<TransactionData> {
+        self.dry_runs.lock().unwrap().clone()
+    }
+}
+
+/// Object ID ending with `index`, e.g. `0x..05` for 5, to tell seeded objects apart at a glance
+pub fn object_id(index: u64) -> ObjectID {
+    let mut bytes = [0; 32];
+    bytes[24..].copy_from_slice(&index.to_be_bytes());
+    ObjectID::new(bytes)
+}
+
+/// SUI coin as returned by the coin read API
+pub fn sui_coin(object_id: ObjectID, balance: u64) -> Coin {
+    serde_json::from_value(json!({
+        "coinType": GAS::type_tag().to_string(),
+        "coinObjectId": object_id,
+        "version": "1",
+        "digest": ObjectDigest::new([0; 32]),
+        "balance": balance.to_string(),
+        "previousTransaction": TransactionDigest::new([0; 32]),
+    }))
+    .expect("Coin JSON should match the RPC types")
+}
+
+/// SUI coin object with the content `fetch_sorted_gas_coins` decodes, as returned by the node
+pub fn sui_coin_object(object_id: ObjectID, balance: u64) -> SuiObjectData {
+    let coin_type = GasCoin::type_().to_string();
+    serde_json::from_value(json!({
+        "objectId": object_id,
+        "version": "1",
+        "digest": ObjectDigest::new([0; 32]),
+        "type": coin_type,
+        "content": {
+            "dataType": "moveObject",
+            "type": coin_type,
+            "hasPublicTransfer": true,
+            "fields": {
+                "balance": balance.to_string(),
+                "id": { "id": object_id },
+            },
+        },
+    }))
+    .expect("Object JSON should match the RPC types")
+}
+
+/// `Coin<coin_type>` object with the raw BCS content `fetch_sorted_coins` decodes, as returned by
+/// the node
+pub fn coin_object(object_id: ObjectID, coin_type: TypeTag, balance: u64) -> SuiObjectData {
+    let type_ = coin::Coin::type_(coin_type);
+    let mut object: SuiObjectData = serde_json::from_value(json!({
+        "objectId": object_id,
+        "version": "1",
+        "digest": ObjectDigest::new([0; 32]),
+        "type": type_.to_string(),
+    }))
+    .expect("Object JSON should match the RPC types");
+    object.bcs = Some(SuiRawData::MoveObject(SuiRawMoveObject {
+        type_,
+        has_public_transfer: true,
+        version: SequenceNumber::from_u64(1),
+        bcs_bytes: bcs::to_bytes(&coin::Coin::new(UID::new(object_id), balance))
+            .expect("Coins should serialize"),
+    }));
+    object
+}
+
+/// Dry run response with `status` and `gas_cost_summary`, without any object or event
+pub fn dry_run_response(
+    status: SuiExecutionStatus,
+    gas_cost_summary: &GasCostSummary,
+) -> DryRunTransactionBlockResponse {
+    let zero_address = SuiAddress::ZERO;
+    let gas_object = (
+        ObjectID::ZERO,
+        SequenceNumber::from_u64(1),
+        ObjectDigest::new([0; 32]),
+    );
+    serde_json::from_value(json!({
+        "effects": effects(status, gas_cost_summary, gas_object),
+        "events": [],
+        "objectChanges": [],
+        "balanceChanges": [],
+        "input": {
+            "messageVersion": "v1",
+            "transaction": {
+                "kind": "ProgrammableTransaction",
+                "inputs": [],
+                "transactions": [],
+            },
+            "sender": zero_address,
+            "gasData": {
+                "payment": [],
+                "owner": zero_address,
+                "price": "1000",
+                "budget": "0",
+            },
+        },
+    }))
+    .expect("Dry run JSON should match the RPC types")
+}
+
+/// Effects of a transaction leaving `gas_object` as its gas coin, without any other object
+pub fn effects(
+    status: SuiExecutionStatus,
+    gas_cost_summary: &GasCostSummary,
+    gas_object: ObjectRef,
+) -> SuiTransactionBlockEffects {
+    let (object_id, version, digest) = gas_object;
+    serde_json::from_value(json!({
+        "messageVersion": "v1",
+        "status": status,
+        "executedEpoch": "0",
+        "gasUsed": gas_cost_summary,
+        "transactionDigest": TransactionDigest::new([0; 32]),
+        "gasObject": {
+            "owner": { "AddressOwner": SuiAddress::ZERO },
+            "reference": {
+                "objectId": object_id,
+                "version": version.value().to_string(),
+                "digest": digest,
+            },
+        },
+        "dependencies": [],
+    }))
+    .expect("Effects JSON should match the RPC types")
+}
+
+/// Dev inspect response of a transaction whose commands returned `return_values`, one list of
+/// BCS-encoded values per command
+pub fn dev_inspect_results(return_values: Vec<Vec<(Vec<u8>, TypeTag)>>) -> DevInspectResults {
+    let results: Vec<_> = return_values
+        .into_iter()
+        .map(|values| {
+            let values: Vec<_> = values
+                .into_iter()
+                .map(|(bytes, type_tag)| json!([bytes, type_tag.to_string()]))
+                .collect();
+            json!({ "mutableReferenceOutputs": [], "returnValues": values })
+        })
+        .collect();
+    dev_inspect_response(SuiExecutionStatus::Success, Some(results), None)
+}
+
+/// Dev inspect response of a transaction aborting with `error`
+pub fn dev_inspect_failure(error: &str) -> DevInspectResults {
+    let status = SuiExecutionStatus::Failure {
+        error: error.to_string(),
+    };
+    dev_inspect_response(status, None, Some(error))
+}
+
+fn dev_inspect_response(
+    status: SuiExecutionStatus,
+    results: Option<Vec<serde_json::Value>>,
+    error: Option<&str>,
+) -> DevInspectResults {
+    let gas_object = (
+        ObjectID::ZERO,
+        SequenceNumber::from_u64(1),
+        ObjectDigest::new([0; 32]),
+    );
+    serde_json::from_value(json!({
+        "effects": effects(status, &GasCostSummary::default(), gas_object),
+        "events": [],
+        "results": results,
+        "error": error,
+    }))
+    .expect("Dev inspect JSON should match the RPC types")
+}
+
+/// Status of a transaction running out of gas, as rendered by the node
+pub fn insufficient_gas_status() -> SuiExecutionStatus {
+    SuiExecutionStatus::Failure {
+        error: format!("{:?}", ExecutionFailureStatus::InsufficientGas),
+    }
+}
+
+/// Methods `json_rpc_response` answers, listed by `rpc.discover`
+const RPC_METHODS: [&str; 6] = [
+    "suix_getOwnedObjects",
+    "suix_getCoins",
+    "suix_getReferenceGasPrice",
+    "sui_getChainIdentifier",
+    "sui_devInspectTransactionBlock",
+    "sui_dryRunTransactionBlock",
+];
+
+/// JSON-RPC response of `node` to a call, as a fullnode would give it for the `NodeApi` methods and
+/// the `rpc.discover` call `SuiClientBuilder` starts with. Node errors become JSON-RPC errors,
+/// malformed calls fail.
+pub(crate) async fn json_rpc_response(
+    node: &dyn NodeApi,
+    method: &str,
+    params: &Value,
+    id: Value,
+) -> anyhow::Result<Value> {
+    let param = |index: usize| params.get(index).cloned().unwrap_or(Value::Null);
+
+    let result = match method {
+        "rpc.discover" => Ok(json!({
+            "info": { "version": "fake" },
+            "methods": RPC_METHODS.map(|name| json!({ "name": name })),
+        })),
+        "suix_getOwnedObjects" => to_json(
+            node.get_owned_objects(
+                from_param(param(0))?,
+                from_param(param(1))?,
+                from_param(param(2))?,
+                from_param(param(3))?,
+            )
+            .await,
+        ),
+        "suix_getCoins" => to_json(
+            node.get_coins(
+                from_param(param(0))?,
+                from_param(param(1))?,
+                from_param(param(2))?,
+                from_param(param(3))?,
+            )
+            .await,
+        ),
+        // Sent as a string, like other `u64`s
+        "suix_getReferenceGasPrice" => node
+            .get_reference_gas_price()
+            .await
+            .map(|price| json!(price.to_string())),
+        "sui_getChainIdentifier" => to_json(node.get_chain_identifier().await),
+        "sui_devInspectTransactionBlock" => {
+            let tx = bcs::from_bytes(&STANDARD.decode(from_param::<String>(param(1))?)?)?;
+            to_json(
+                node.dev_inspect_transaction_block(from_param(param(0))?, tx)
+                    .await,
+            )
+        }
+        "sui_dryRunTransactionBlock" => {
+            let tx = bcs::from_bytes(&STANDARD.decode(from_param::<String>(param(0))?)?)?;
+            to_json(node.dry_run_transaction_block(tx).await)
+        }
+        _ => anyhow::bail!("{} is not served by the fake node", method),
+    };
+
+    Ok(match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(e) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": -32000, "message": e.to_string() },
+        }),
+    })
+}
+
+fn from_param<T: DeserializeOwned>(param: Value) -> anyhow::Result<T> {
+    Ok(serde_json::from_value(param)?)
+}
+
+fn to_json<T: Serialize>(result: Result<T>) -> Result<Value> {
+    result.map(|value| serde_json::to_value(value).expect("RPC types serialize to JSON"))
+}
+
+/// Serve `items` one page at a time, cursors being the ID of the last item of the previous page
+fn paginate<T: Clone>(
+    items: &[T],
+    id: impl Fn(&T) -> ObjectID,
+    cursor: Option<ObjectID>,
+    limit: usize,
+) -> Page<T, ObjectID> {
+    let start = match cursor {
+        Some(cursor) => items
+            .iter()
+            .position(|item| id(item) == cursor)
+            .map_or(items.len(), |i| i + 1),
+        None => 0,
+    };
+    let end = (start + limit).min(items.len());
+    let data = items[start..end].to_vec();
+
+    Page {
+        next_cursor: data.last().map(&id),
+        has_next_page: end < items.len(),
+        data,
+    }
+}
+
+/// Whether `object` matches `filter`. A struct type without type parameters matches all its
+/// instantiations, e.g. `0x2::coin::Coin` matches `0x2::coin::Coin<0x2::sui::SUI>`.
+fn matches_filter(filter: &SuiObjectDataFilter, object: &SuiObjectData) -> Result<bool> {
+    let struct_tag =
+        || -> Option<StructTag> { parse_sui_struct_tag(&object.type_.as_ref()?.to_string()).ok() };
+
+    Ok(match filter {
+        SuiObjectDataFilter::MatchAll(filters) => {
+            for filter in filters {
+                if !matches_filter(filter, object)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        SuiObjectDataFilter::MatchAny(filters) => {
+            for filter in filters {
+                if matches_filter(filter, object)? {
+                    return Ok(true);
+                }
+            }
+            false
+        }
+        SuiObjectDataFilter::MatchNone(filters) => {
+            for filter in filters {
+                if matches_filter(filter, object)? {
+                    return Ok(false);
+                }
+            }
+            true
+        }
+        SuiObjectDataFilter::StructType(expected) => struct_tag().is_some_and(|struct_tag| {
+            struct_tag.address == expected.address
+                && struct_tag.module == expected.module
+                && struct_tag.name == expected.name
+                && (expected.type_params.is_empty()
+                    || struct_tag.type_params == expected.type_params)
+        }),
+        SuiObjectDataFilter::Package(package) => {
+            struct_tag().is_some_and(|struct_tag| ObjectID::from(struct_tag.address) == *package)
+        }
+        SuiObjectDataFilter::MoveModule { package, module } => {
+            struct_tag().is_some_and(|struct_tag| {
+                ObjectID::from(struct_tag.address) == *package && struct_tag.module == *module
+            })
+        }
+        SuiObjectDataFilter::ObjectId(object_id) => object.object_id == *object_id,
+        SuiObjectDataFilter::ObjectIds(object_ids) => object_ids.contains(&object.object_id),
+        filter => {
+            return Err(Error::Node(format!(
+                "Filter {:?} is not supported by the fake node",
+                filter
+            )))
+        }
+    })
+}
+
+#[async_trait]
+impl NodeApi for FakeNode {
+    async fn get_owned_objects(
+        &self,
+        owner: SuiAddress,
+        query: Option<SuiObjectResponseQuery>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<ObjectsPage> {
+        let filter = query.and_then(|query| query.filter);
+        let mut objects = vec![];
+        for object in self.owned_objects.get(&owner).into_iter().flatten() {
+            let matches = match &filter {
+                Some(filter) => matches_filter(filter, object)?,
+                None => true,
+            };
+            if matches {
+                objects.push(object.clone());
+            }
+        }
+        let page = paginate(
+            &objects,
+            |object| object.object_id,
+            cursor,
+            limit.unwrap_or(self.page_size),
+        );
+
+        Ok(Page {
+            data: page
+                .data
+                .into_iter()
+                .map(SuiObjectResponse::new_with_data)
+                .collect(),
+            next_cursor: page.next_cursor,
+            has_next_page: page.has_next_page,
+        })
+    }
+
+    async fn get_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: Option<String>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<CoinPage> {
+        // compare parsed tags, as addresses can be written both short and long
+        let coin_type = match coin_type {
+            Some(coin_type) => TypeTag::from_str(&coin_type)
+                .map_err(|e| Error::Node(format!("Invalid coin type {}: {}", coin_type, e)))?,
+            None => GAS::type_tag(),
+        };
+        let coins: Vec<Coin> = self
+            .coins
+            .get(&owner)
+            .into_iter()
+            .flatten()
+            .filter(|coin| TypeTag::from_str(&coin.coin_type).ok().as_ref() == Some(&coin_type))
+            .cloned()
+            .collect();
+
+        Ok(paginate(
+            &coins,
+            |coin| coin.coin_object_id,
+            cursor,
+            limit.unwrap_or(self.page_size),
+        ))
+    }
+
+    async fn get_reference_gas_price(&self) -> Result<u64> {
+        Ok(self.reference_gas_price)
+    }
+
+    async fn get_chain_identifier(&self) -> Result<String> {
+        Ok(self.chain_identifier.clone())
+    }
+
+    async fn dev_inspect_transaction_block(
+        &self,
+        sender: SuiAddress,
+        tx: TransactionKind,
+    ) -> Result<DevInspectResults> {
+        if let Some(response) = self.dev_inspect_script.lock().unwrap().pop_front() {
+            return response;
+        }
+        match &self.dev_inspect_handler {
+            Some(handler) => handler(sender, &tx),
+            None => Err(Error::Node("No dev inspect response scripted".to_string())),
+        }
+    }
+
+    async fn dry_run_transaction_block(
+        &self,
+        tx: TransactionData,
+    ) -> Result<DryRunTransactionBlockResponse> {
+        self.dry_runs.lock().unwrap().push(tx.clone());
+
+        if let Some(response) = self.dry_run_script.lock().unwrap().pop_front() {
+            return response;
+        }
+        match &self.dry_run_handler {
+            Some(handler) => handler(&tx),
+            None => Err(Error::Node("No dry run response scripted".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+    use sui_sdk::rpc_types::{SuiObjectDataOptions, SuiTransactionBlockEffectsAPI};
+    use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+
+    use super::*;
+    use crate::{fetch_sorted_gas_coin_refs, fetch_sorted_gas_coins, owned_objects_stream};
+
+    const OWNER: SuiAddress = SuiAddress::ZERO;
+
+    fn object(object_id: ObjectID, type_: &str) -> SuiObjectData {
+        serde_json::from_value(json!({
+            "objectId": object_id,
+            "version": "1",
+            "digest": ObjectDigest::new([0; 32]),
+            "type": type_,
+        }))
+        .unwrap()
+    }
+
+    async fn owned_ids(node: &FakeNode, filter: SuiObjectDataFilter) -> Vec<ObjectID> {
+        let query = SuiObjectResponseQuery {
+            filter: Some(filter),
+            options: Some(SuiObjectDataOptions::new()),
+        };
+        owned_objects_stream(node, OWNER, query, None)
+            .map_ok(|object| object.object_id)
+            .try_collect()
+            .await
+            .unwrap()
+    }
+
+    fn node_with_objects() -> FakeNode {
+        FakeNode::new().with_page_size(2).with_owned_objects(
+            OWNER,
+            vec![
+                sui_coin_object(object_id(1), 10),
+                object(object_id(2), "0x2::coin::Coin<0xabc::usdc::USDC>"),
+                object(object_id(3), "0xabc::pool::Pool"),
+                sui_coin_object(object_id(4), 30),
+                object(object_id(5), "0xdef::pool::Pool"),
+            ],
+        )
+    }
+
+    #[tokio::test]
+    async fn owned_objects_are_filtered_across_pages() {
+        let node = node_with_objects();
+
+        let coins =
+            SuiObjectDataFilter::StructType(parse_sui_struct_tag("0x2::coin::Coin").unwrap());
+        assert_eq!(
+            owned_ids(&node, coins).await,
+            vec![object_id(1), object_id(2), object_id(4)]
+        );
+
+        let gas_coins = SuiObjectDataFilter::StructType(GasCoin::type_());
+        assert_eq!(
+            owned_ids(&node, gas_coins).await,
+            vec![object_id(1), object_id(4)]
+        );
+
+        let pools = SuiObjectDataFilter::MoveModule {
+            package: ObjectID::from_hex_literal("0xabc").unwrap(),
+            module: "pool".parse().unwrap(),
+        };
+        assert_eq!(owned_ids(&node, pools).await, vec![object_id(3)]);
+
+        let not_abc = SuiObjectDataFilter::MatchNone(vec![SuiObjectDataFilter::Package(
+            ObjectID::from_hex_literal("0xabc").unwrap(),
+        )]);
+        assert_eq!(
+            owned_ids(&node, not_abc).await,
+            vec![object_id(1), object_id(2), object_id(4), object_id(5)]
+        );
+
+        let some = SuiObjectDataFilter::MatchAny(vec![
+            SuiObjectDataFilter::ObjectId(object_id(5)),
+            SuiObjectDataFilter::ObjectIds(vec![object_id(1), object_id(3)]),
+        ]);
+        assert_eq!(
+            owned_ids(&node, some).await,
+            vec![object_id(1), object_id(3), object_id(5)]
+        );
+    }
+
+    #[tokio::test]
+    async fn unsupported_filters_are_rejected() {
+        let node = node_with_objects();
+        let query = SuiObjectResponseQuery {
+            filter: Some(SuiObjectDataFilter::AddressOwner(OWNER)),
+            options: None,
+        };
+        let result = node.get_owned_objects(OWNER, Some(query), None, None).await;
+        assert!(matches!(result, Err(Error::Node(_))));
+    }
+
+    #[tokio::test]
+    async fn gas_coins_are_fetched_sorted_by_balance() {
+        let node = node_with_objects().with_coins(
+            OWNER,
+            vec![
+                sui_coin(object_id(6), 20),
+                sui_coin(object_id(7), 0),
+                sui_coin(object_id(8), 50),
+                sui_coin(object_id(9), 5),
+            ],
+        );
+
+        let gas_coins = fetch_sorted_gas_coins(&node, &OWNER).await.unwrap();
+        let gas_coins: Vec<_> = gas_coins
+            .iter()
+            .map(|(object, balance)| (object.object_id, *balance))
+            .collect();
+        assert_eq!(gas_coins, vec![(object_id(4), 30), (object_id(1), 10)]);
+
+        let coin_refs = fetch_sorted_gas_coin_refs(&node, &OWNER).await.unwrap();
+        let coin_refs: Vec<_> = coin_refs
+            .iter()
+            .map(|(object_ref, balance)| (object_ref.0, *balance))
+            .collect();
+        assert_eq!(
+            coin_refs,
+            vec![(object_id(8), 50), (object_id(6), 20), (object_id(9), 5)]
+        );
+    }
+
+    #[tokio::test]
+    async fn min_gas_budget_fails_low_budgets() {
+        let cost = GasCostSummary {
+            computation_cost: 1_000,
+            storage_cost: 2_000,
+            storage_rebate: 500,
+            non_refundable_storage_fee: 0,
+        };
+        let node = FakeNode::new().with_min_gas_budget(3_000, cost.clone());
+        let pt = ProgrammableTransactionBuilder::new().finish();
+        let tx = |gas_budget| {
+            TransactionData::new_programmable(OWNER, vec![], pt.clone(), gas_budget, 1_000)
+        };
+
+        let response = node.dry_run_transaction_block(tx(2_999)).await.unwrap();
+        assert_eq!(*response.effects.status(), insufficient_gas_status());
+
+        let response = node.dry_run_transaction_block(tx(3_000)).await.unwrap();
+        assert_eq!(*response.effects.status(), SuiExecutionStatus::Success);
+        assert_eq!(*response.effects.gas_cost_summary(), cost);
+        assert_eq!(node.dry_runs().len(), 2);
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{anyhow, ensure};
+use sui_sdk::rpc_types::{SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI};
+use sui_types::{
+    base_types::{ObjectRef, SuiAddress},
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{Argument, Command, ObjectArg, ProgrammableTransaction},
+};
+
+use crate::limits::MAX_ARGUMENTS;
+
+/// Coin split by a fan-out PTB
+#[derive(Debug, Clone, Copy)]
+pub enum FanOutSource {
+    /// The gas coin, its balance being shared with the gas budget
+    GasCoin,
+    /// An owned coin not used for gas, e.g. the richest one from `fetch_sorted_gas_coins`
+    Coin(ObjectRef),
+}
+
+/// Build a PTB turning `source` into `count` coins of roughly equal value, all owned by `sender`.
+///
+/// `count - 1` coins are split from the source with a single `SplitCoins` and transferred back to
+/// the sender, the source itself being the last one. When splitting the gas coin, `gas_budget` is
+/// set aside before dividing its balance.
+pub fn build_fan_out_ptb(
+    sender: SuiAddress,
+    source: FanOutSource,
+    source_balance: u64,
+    count: usize,
+    gas_budget: u64,
+) -> anyhow::Result<ProgrammableTransaction> {
+    ensure!(
+        count > 1,
+        "Fanning out needs at least 2 coins, got {}",
+        count
+    );
+    // `SplitCoins` takes the source and one amount per new coin, `TransferObjects` every new coin
+    // plus the recipient
+    ensure!(
+        count <= MAX_ARGUMENTS,
+        "Cannot fan out into {} coins, a command takes at most {} arguments",
+        count,
+        MAX_ARGUMENTS
+    );
+
+    let splittable = match source {
+        FanOutSource::GasCoin => source_balance
+            .checked_sub(gas_budget)
+            .ok_or_else(|| anyhow!("Gas coin balance does not cover the gas budget"))?,
+        FanOutSource::Coin(_) => source_balance,
+    };
+    let amount = splittable / count as u64;
+    ensure!(amount > 0, "Balance too low to split into {} coins", count);
+
+    let mut pt_builder = ProgrammableTransactionBuilder::new();
+    let source_arg = match source {
+        FanOutSource::GasCoin => Argument::GasCoin,
+        FanOutSource::Coin(coin) => pt_builder.obj(ObjectArg::ImmOrOwnedObject(coin))?,
+    };
+    let amount_arg = pt_builder.pure(amount)?;
+
+    let new_coin_count = count - 1;
+    let Argument::Result(split_result) = pt_builder.command(Command::SplitCoins(
+        source_arg,
+        vec![amount_arg; new_coin_count],
+    )) else {
+        unreachable!("Commands always return a Result")
+    };
+    let new_coins = (0..new_coin_count as u16)
+        .map(|i| Argument::NestedResult(split_result, i))
+        .collect();
+    pt_builder.transfer_args(sender, new_coins);
+
+    Ok(pt_builder.finish())
+}
+
+/// Read the refs of the fanned out coins from the effects of a fan-out transaction: every created
+/// coin plus the new version of the source.
+pub fn fan_out_coins(
+    effects: &SuiTransactionBlockEffects,
+    source: &FanOutSource,
+) -> anyhow::Result<Vec<ObjectRef>> {
+    let source_ref = match source {
+        FanOutSource::GasCoin => effects.gas_object().reference.to_object_ref(),
+        FanOutSource::Coin((source_id, _, _)) => effects
+            .mutated()
+            .iter()
+            .map(|object| object.reference.to_object_ref())
+            .find(|(id, _, _)| id == source_id)
+            .ok_or_else(|| anyhow!("Source coin {} not found in mutated objects", source_id))?,
+    };
+
+    let mut coins: Vec<ObjectRef> = effects
+        .created()
+        .iter()
+        .map(|object| object.reference.to_object_ref())
+        .collect();
+    coins.push(source_ref);
+
+    Ok(coins)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use serde_json::json;
+    use sui_sdk::rpc_types::SuiExecutionStatus;
+    use sui_types::{
+        base_types::{ObjectDigest, SequenceNumber},
+        gas::GasCostSummary,
+        transaction::CallArg,
+    };
+
+    use super::*;
+    use crate::fake::{effects, object_id};
+
+    const GAS_BUDGET: u64 = 10_000_000;
+
+    fn coin_ref(index: u64, version: u64) -> ObjectRef {
+        (
+            object_id(index),
+            SequenceNumber::from_u64(version),
+            ObjectDigest::new([0; 32]),
+        )
+    }
+
+    /// Amount of each new coin, the only `u64` input
+    fn split_amount(pt: &ProgrammableTransaction) -> u64 {
+        let Command::SplitCoins(_, amounts) = &pt.commands[0] else {
+            panic!("Expected a split, got {:?}", pt.commands[0]);
+        };
+        let Argument::Input(input) = amounts[0] else {
+            panic!("Expected an input amount, got {:?}", amounts[0]);
+        };
+        let CallArg::Pure(bytes) = &pt.inputs[input as usize] else {
+            panic!(
+                "Expected a pure amount, got {:?}",
+                pt.inputs[input as usize]
+            );
+        };
+        bcs::from_bytes(bytes).unwrap()
+    }
+
+    /// Effects of a fan-out paid by `gas_object`, creating `created` and mutating `mutated`
+    fn fan_out_effects(
+        gas_object: ObjectRef,
+        created: &[ObjectRef],
+        mutated: &[ObjectRef],
+    ) -> SuiTransactionBlockEffects {
+        let owned = |refs: &[ObjectRef]| -> Vec<_> {
+            refs.iter()
+                .map(|(object_id, version, digest)| {
+                    json!({
+                        "owner": { "AddressOwner": SuiAddress::ZERO },
+                        "reference": {
+                            "objectId": object_id,
+                            "version": version.value().to_string(),
+                            "digest": digest,
+                        },
+                    })
+                })
+                .collect()
+        };
+        let mut effects = serde_json::to_value(effects(
+            SuiExecutionStatus::Success,
+            &GasCostSummary::default(),
+            gas_object,
+        ))
+        .unwrap();
+        effects["created"] = json!(owned(created));
+        effects["mutated"] = json!(owned(mutated));
+        serde_json::from_value(effects).unwrap()
+    }
+
+    #[test]
+    fn counts_outside_the_argument_limit_are_rejected() {
+        let source = FanOutSource::Coin(coin_ref(1, 1));
+        for count in [0, 1, MAX_ARGUMENTS + 1] {
+            assert!(
+                build_fan_out_ptb(SuiAddress::ZERO, source, 1_000_000, count, GAS_BUDGET).is_err()
+            );
+        }
+        assert!(build_fan_out_ptb(
+            SuiAddress::ZERO,
+            source,
+            1_000_000,
+            MAX_ARGUMENTS,
+            GAS_BUDGET
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn gas_coin_keeps_the_gas_budget() {
+        let pt = build_fan_out_ptb(
+            SuiAddress::ZERO,
+            FanOutSource::GasCoin,
+            1_000_000_000,
+            4,
+            GAS_BUDGET,
+        )
+        .unwrap();
+
+        assert_eq!(split_amount(&pt), (1_000_000_000 - GAS_BUDGET) / 4);
+        let Command::SplitCoins(source, amounts) = &pt.commands[0] else {
+            unreachable!()
+        };
+        assert_eq!(*source, Argument::GasCoin);
+        assert_eq!(amounts.len(), 3);
+        assert!(matches!(
+            &pt.commands[1],
+            Command::TransferObjects(coins, _) if coins.len() == 3
+        ));
+    }
+
+    #[test]
+    fn gas_coin_short_of_the_gas_budget_is_rejected() {
+        let result = build_fan_out_ptb(
+            SuiAddress::ZERO,
+            FanOutSource::GasCoin,
+            GAS_BUDGET - 1,
+            2,
+            GAS_BUDGET,
+        );
+        assert!(result.is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn amounts_are_equal_and_fit_the_balance(
+            balance in 2..u64::MAX,
+            count in 2..=MAX_ARGUMENTS,
+        ) {
+            let source = FanOutSource::Coin(coin_ref(1, 1));
+            let Ok(pt) = build_fan_out_ptb(SuiAddress::ZERO, source, balance, count, GAS_BUDGET)
+            else {
+                // Only balances too low to split are rejected
+                prop_assert!(balance < count as u64);
+                return Ok(());
+            };
+
+            let amount = split_amount(&pt) as u128;
+            let Command::SplitCoins(_, amounts) = &pt.commands[0] else {
+                unreachable!()
+            };
+            prop_assert!(amounts.iter().all(|argument| *argument == amounts[0]));
+            // The source keeps the remainder on top of its share
+            prop_assert!(amount * count as u128 <= balance as u128);
+            prop_assert!(balance as u128 - amount * count as u128 < count as u128);
+        }
+    }
+
+    #[test]
+    fn fanned_out_coins_are_read_from_the_effects() {
+        let new_coins = [coin_ref(2, 5), coin_ref(3, 5)];
+
+        let effects = fan_out_effects(coin_ref(1, 5), &new_coins, &[]);
+        assert_eq!(
+            fan_out_coins(&effects, &FanOutSource::GasCoin).unwrap(),
+            vec![new_coins[0], new_coins[1], coin_ref(1, 5)]
+        );
+
+        let source = FanOutSource::Coin(coin_ref(4, 1));
+        let effects = fan_out_effects(coin_ref(1, 5), &new_coins, &[coin_ref(4, 5)]);
+        assert_eq!(
+            fan_out_coins(&effects, &source).unwrap(),
+            vec![new_coins[0], new_coins[1], coin_ref(4, 5)]
+        );
+
+        let effects = fan_out_effects(coin_ref(1, 5), &new_coins, &[]);
+        assert!(fan_out_coins(&effects, &source).is_err());
+    }
+}
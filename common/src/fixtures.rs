@@ -0,0 +1,523 @@
+//! Record-and-replay of the JSON-RPC traffic between a `SuiClient` and a fullnode.
+//!
+//! Both modes run a local HTTP server the client connects to instead of the fullnode: when
+//! recording it forwards every request upstream and stores each request/response pair, when
+//! replaying it answers from the stored pairs without any network access.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    convert::Infallible,
+    net::{SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, bail, Context};
+use hyper::{
+    service::{make_service_fn, service_fn},
+    Body, Request, Response, Server, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::{sync::oneshot, task::JoinHandle};
+
+use crate::Network;
+
+/// Env var selecting the mode: `record`, `replay`, or unset for live traffic
+pub const FIXTURE_MODE_ENV: &str = "SUI_RPC_FIXTURE_MODE";
+/// Env var holding the path of the fixture file to record to or replay from
+pub const FIXTURE_PATH_ENV: &str = "SUI_RPC_FIXTURE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FixtureMode {
+    Live,
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+impl FixtureMode {
+    /// Read the mode from `SUI_RPC_FIXTURE_MODE`, falling back to `default_path` when
+    /// `SUI_RPC_FIXTURE` is not set.
+    pub fn from_env(default_path: impl Into<PathBuf>) -> anyhow::Result<Self> {
+        let path = std::env::var(FIXTURE_PATH_ENV)
+            .map(PathBuf::from)
+            .unwrap_or_else(|_| default_path.into());
+
+        match std::env::var(FIXTURE_MODE_ENV).as_deref() {
+            Err(_) | Ok("") | Ok("live") => Ok(Self::Live),
+            Ok("record") => Ok(Self::Record(path)),
+            Ok("replay") => Ok(Self::Replay(path)),
+            Ok(mode) => bail!("Unknown {} {:?}", FIXTURE_MODE_ENV, mode),
+        }
+    }
+}
+
+/// Fixture file for `network` in `dir`, e.g. `<dir>/testnet.json`, so that recording against one
+/// network never overwrites the recording of another. Custom URLs are flattened into the name.
+pub fn network_fixture_path(dir: impl AsRef<Path>, network: &Network) -> PathBuf {
+    let name: String = network
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '.' => c,
+            _ => '_',
+        })
+        .collect();
+    dir.as_ref().join(format!("{}.json", name))
+}
+
+/// A JSON-RPC call and the node's answer, minus the request ID
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    pub params: Value,
+    pub response: Value,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Fixture {
+    pub interactions: Vec<Interaction>,
+}
+
+/// Where forwarded calls are answered
+enum Upstream {
+    Http {
+        url: String,
+        http_client: reqwest::Client,
+    },
+    /// In-memory node, e.g. a `FakeNode`, answering as a fullnode would
+    #[cfg(any(test, feature = "fake"))]
+    Node(Arc<dyn crate::NodeApi>),
+}
+
+enum Backend {
+    /// Calls forwarded upstream, and stored when recording
+    Forward {
+        upstream: Upstream,
+        recorded: Option<Mutex<Vec<Interaction>>>,
+    },
+    /// Responses queued per `(method, params)`, the last one being served again once the queue
+    /// runs out
+    Replay {
+        responses: Mutex<HashMap<(String, String), VecDeque<Value>>>,
+    },
+}
+
+/// Handle on the local server, to be `finish`ed once the client is done so the recording is saved.
+pub struct FixtureServer {
+    url: String,
+    mode: FixtureMode,
+    backend: Option<Arc<Backend>>,
+    shutdown: Option<(oneshot::Sender<()>, JoinHandle<Result<(), hyper::Error>>)>,
+}
+
+impl FixtureServer {
+    /// Start serving `upstream` in the given mode. In `FixtureMode::Live` no server is started and
+    /// `url` is `upstream` itself.
+    pub async fn start(mode: FixtureMode, upstream: &str) -> anyhow::Result<Self> {
+        if mode == FixtureMode::Live {
+            return Ok(Self {
+                url: upstream.to_string(),
+                mode,
+                backend: None,
+                shutdown: None,
+            });
+        }
+        let upstream = Upstream::Http {
+            url: upstream.to_string(),
+            http_client: reqwest::Client::new(),
+        };
+        Self::serve(mode, upstream).await
+    }
+
+    /// Same as `start`, with `node` answering in place of a fullnode, e.g. to record a session
+    /// against a `FakeNode`. In `FixtureMode::Live` the node is served as is.
+    #[cfg(any(test, feature = "fake"))]
+    pub async fn start_with_node(
+        mode: FixtureMode,
+        node: Arc<dyn crate::NodeApi>,
+    ) -> anyhow::Result<Self> {
+        Self::serve(mode, Upstream::Node(node)).await
+    }
+
+    async fn serve(mode: FixtureMode, upstream: Upstream) -> anyhow::Result<Self> {
+        let backend = match &mode {
+            FixtureMode::Live => Backend::Forward {
+                upstream,
+                recorded: None,
+            },
+            FixtureMode::Record(_) => Backend::Forward {
+                upstream,
+                recorded: Some(Mutex::new(vec![])),
+            },
+            FixtureMode::Replay(path) => {
+                let content = std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read fixture {}", path.display()))?;
+                let fixture: Fixture = serde_json::from_str(&content)?;
+
+                let mut responses: HashMap<(String, String), VecDeque<Value>> = HashMap::new();
+                for interaction in fixture.interactions {
+                    responses
+                        .entry(key(&interaction.method, &interaction.params))
+                        .or_default()
+                        .push_back(interaction.response);
+                }
+                Backend::Replay {
+                    responses: Mutex::new(responses),
+                }
+            }
+        };
+        let backend = Arc::new(backend);
+
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))?;
+        let url = format!("http://{}", listener.local_addr()?);
+
+        let service_backend = backend.clone();
+        let make_service = make_service_fn(move |_| {
+            let backend = service_backend.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| handle(backend.clone(), request)))
+            }
+        });
+
+        let (shutdown_sender, shutdown_receiver) = oneshot::channel::<()>();
+        let server = Server::from_tcp(listener)?
+            .serve(make_service)
+            .with_graceful_shutdown(async {
+                shutdown_receiver.await.ok();
+            });
+        let server_handle = tokio::spawn(server);
+
+        Ok(Self {
+            url,
+            mode,
+            backend: Some(backend),
+            shutdown: Some((shutdown_sender, server_handle)),
+        })
+    }
+
+    /// URL to build the `SuiClient` with
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Stop the server, and write the fixture file when recording.
+    pub async fn finish(mut self) -> anyhow::Result<()> {
+        if let Some((shutdown_sender, server_handle)) = self.shutdown.take() {
+            shutdown_sender.send(()).ok();
+            server_handle.await??;
+        }
+
+        if let (FixtureMode::Record(path), Some(backend)) = (&self.mode, &self.backend) {
+            if let Backend::Forward {
+                recorded: Some(recorded),
+                ..
+            } = backend.as_ref()
+            {
+                let fixture = Fixture {
+                    interactions: recorded.lock().unwrap().clone(),
+                };
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                std::fs::write(path, serde_json::to_string_pretty(&fixture)?)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn key(method: &str, params: &Value) -> (String, String) {
+    (method.to_string(), params.to_string())
+}
+
+async fn handle(
+    backend: Arc<Backend>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let response = match serve(&backend, request).await {
+        Ok(body) => Response::builder()
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        Err(e) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .body(Body::from(e.to_string())),
+    };
+
+    Ok(response.expect("Static response parts are valid"))
+}
+
+async fn serve(backend: &Backend, request: Request<Body>) -> anyhow::Result<Value> {
+    let body = hyper::body::to_bytes(request.into_body()).await?;
+    let body: Value = serde_json::from_slice(&body)?;
+
+    // JSON-RPC batches are served call by call
+    match body {
+        Value::Array(calls) => {
+            let mut responses = vec![];
+            for call in calls {
+                responses.push(serve_call(backend, call).await?);
+            }
+            Ok(Value::Array(responses))
+        }
+        call => serve_call(backend, call).await,
+    }
+}
+
+async fn serve_call(backend: &Backend, call: Value) -> anyhow::Result<Value> {
+    let method = call["method"]
+        .as_str()
+        .ok_or_else(|| anyhow!("JSON-RPC call without a method: {}", call))?
+        .to_string();
+    let params = call.get("params").cloned().unwrap_or(Value::Null);
+    let id = call.get("id").cloned().unwrap_or(Value::Null);
+
+    match backend {
+        Backend::Forward { upstream, recorded } => {
+            let response: Value = match upstream {
+                Upstream::Http { url, http_client } => {
+                    http_client
+                        .post(url)
+                        .json(&call)
+                        .send()
+                        .await?
+                        .json()
+                        .await?
+                }
+                #[cfg(any(test, feature = "fake"))]
+                Upstream::Node(node) => {
+                    crate::fake::json_rpc_response(node.as_ref(), &method, &params, id).await?
+                }
+            };
+            if let Some(recorded) = recorded {
+                recorded.lock().unwrap().push(Interaction {
+                    method,
+                    params,
+                    response: response.clone(),
+                });
+            }
+            Ok(response)
+        }
+        Backend::Replay { responses } => {
+            let mut responses = responses.lock().unwrap();
+            let queue = responses
+                .get_mut(&key(&method, &params))
+                .ok_or_else(|| anyhow!("No recorded response for {} {}", method, params))?;
+            let mut response = if queue.len() > 1 {
+                queue.pop_front().unwrap()
+            } else {
+                queue.front().cloned().unwrap()
+            };
+            response["id"] = id;
+            Ok(response)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use sui_sdk::SuiClientBuilder;
+    use sui_types::base_types::SuiAddress;
+
+    use super::*;
+    use crate::{
+        fake::{object_id, sui_coin},
+        FakeNode, NodeApi,
+    };
+
+    fn fixture_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("sui-fixture-{}-{}.json", name, std::process::id()))
+    }
+
+    fn write_fixture(path: &PathBuf, interactions: Vec<Interaction>) {
+        let fixture = Fixture { interactions };
+        std::fs::write(path, serde_json::to_string(&fixture).unwrap()).unwrap();
+    }
+
+    fn interaction(method: &str, params: Value, result: Value) -> Interaction {
+        Interaction {
+            method: method.to_string(),
+            params,
+            response: json!({ "jsonrpc": "2.0", "id": 0, "result": result }),
+        }
+    }
+
+    async fn call(server: &FixtureServer, id: u64, method: &str, params: Value) -> (u16, String) {
+        let response = reqwest::Client::new()
+            .post(server.url())
+            .json(&json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+            .send()
+            .await
+            .unwrap();
+        (response.status().as_u16(), response.text().await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn replay_serves_responses_in_order_then_repeats_the_last() {
+        let path = fixture_path("replay");
+        write_fixture(
+            &path,
+            vec![
+                interaction("suix_getReferenceGasPrice", json!([]), json!("750")),
+                interaction("suix_getReferenceGasPrice", json!([]), json!("1000")),
+            ],
+        );
+        let server = FixtureServer::start(FixtureMode::Replay(path.clone()), "http://unused")
+            .await
+            .unwrap();
+
+        for (id, expected) in [(7, "750"), (8, "1000"), (9, "1000")] {
+            let (status, body) = call(&server, id, "suix_getReferenceGasPrice", json!([])).await;
+            let body: Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(status, 200);
+            assert_eq!(body["result"], expected);
+            // Clients match responses by the ID of their request, not the recorded one
+            assert_eq!(body["id"], id);
+        }
+
+        let (status, _) = call(&server, 10, "sui_getChainIdentifier", json!([])).await;
+        assert_eq!(status, 500);
+
+        server.finish().await.unwrap();
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn recordings_replay_the_upstream_answers() {
+        // A replaying server stands in for the fullnode being recorded
+        let upstream_path = fixture_path("upstream");
+        write_fixture(
+            &upstream_path,
+            vec![
+                interaction("sui_getChainIdentifier", json!([]), json!("35834a8a")),
+                interaction(
+                    "suix_getCoins",
+                    json!(["0x1", null, null, null]),
+                    json!({ "data": [] }),
+                ),
+            ],
+        );
+        let upstream = FixtureServer::start(FixtureMode::Replay(upstream_path.clone()), "")
+            .await
+            .unwrap();
+
+        let recording_path = fixture_path("recording");
+        let recorder =
+            FixtureServer::start(FixtureMode::Record(recording_path.clone()), upstream.url())
+                .await
+                .unwrap();
+        let recorded = [
+            call(&recorder, 1, "sui_getChainIdentifier", json!([])).await,
+            call(
+                &recorder,
+                2,
+                "suix_getCoins",
+                json!(["0x1", null, null, null]),
+            )
+            .await,
+        ];
+        recorder.finish().await.unwrap();
+        upstream.finish().await.unwrap();
+
+        let fixture: Fixture =
+            serde_json::from_str(&std::fs::read_to_string(&recording_path).unwrap()).unwrap();
+        let methods: Vec<_> = fixture
+            .interactions
+            .iter()
+            .map(|i| i.method.as_str())
+            .collect();
+        assert_eq!(methods, vec!["sui_getChainIdentifier", "suix_getCoins"]);
+
+        let replay = FixtureServer::start(FixtureMode::Replay(recording_path.clone()), "")
+            .await
+            .unwrap();
+        let replayed = [
+            call(&replay, 1, "sui_getChainIdentifier", json!([])).await,
+            call(
+                &replay,
+                2,
+                "suix_getCoins",
+                json!(["0x1", null, null, null]),
+            )
+            .await,
+        ];
+        replay.finish().await.unwrap();
+
+        for ((recorded_status, recorded), (replayed_status, replayed)) in
+            recorded.iter().zip(&replayed)
+        {
+            assert_eq!(*recorded_status, 200);
+            assert_eq!(replayed_status, recorded_status);
+            let recorded: Value = serde_json::from_str(recorded).unwrap();
+            let replayed: Value = serde_json::from_str(replayed).unwrap();
+            assert_eq!(replayed, recorded);
+        }
+
+        std::fs::remove_file(upstream_path).unwrap();
+        std::fs::remove_file(recording_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn live_mode_talks_to_the_upstream_directly() {
+        let server = FixtureServer::start(FixtureMode::Live, "https://fullnode.example")
+            .await
+            .unwrap();
+        assert_eq!(server.url(), "https://fullnode.example");
+        server.finish().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn node_sessions_replay_through_the_sdk() {
+        let owner = SuiAddress::ZERO;
+        let node = FakeNode::new()
+            .with_coins(owner, vec![sui_coin(object_id(1), 10)])
+            .with_reference_gas_price(750);
+        let path = fixture_path("node");
+        let recorder =
+            FixtureServer::start_with_node(FixtureMode::Record(path.clone()), Arc::new(node))
+                .await
+                .unwrap();
+        let rpc_client = SuiClientBuilder::default()
+            .build(recorder.url())
+            .await
+            .unwrap();
+        let coins = NodeApi::get_coins(&rpc_client, owner, None, None, None).await;
+        let chain_identifier = NodeApi::get_chain_identifier(&rpc_client).await;
+        recorder.finish().await.unwrap();
+        assert_eq!(coins.unwrap().data, vec![sui_coin(object_id(1), 10)]);
+        assert_eq!(chain_identifier.unwrap(), "fakenet");
+
+        let replay = FixtureServer::start(FixtureMode::Replay(path.clone()), "")
+            .await
+            .unwrap();
+        let rpc_client = SuiClientBuilder::default()
+            .build(replay.url())
+            .await
+            .unwrap();
+        let coins = NodeApi::get_coins(&rpc_client, owner, None, None, None).await;
+        // Not recorded
+        let gas_price = NodeApi::get_reference_gas_price(&rpc_client).await;
+        replay.finish().await.unwrap();
+        assert_eq!(coins.unwrap().data, vec![sui_coin(object_id(1), 10)]);
+        assert!(gas_price.is_err());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn fixtures_are_named_after_the_network() {
+        assert_eq!(
+            network_fixture_path("fixtures", &Network::Testnet),
+            Path::new("fixtures/testnet.json")
+        );
+        assert_eq!(
+            network_fixture_path(
+                "fixtures",
+                &Network::Custom("http://127.0.0.1:9100".to_string())
+            ),
+            Path::new("fixtures/http___127.0.0.1_9100.json")
+        );
+    }
+}
@@ -0,0 +1,394 @@
+//! Rust mirrors of Sui framework types, and `MoveFunction` descriptors of the framework functions
+//! used in the examples.
+
+use std::{marker::PhantomData, str::FromStr};
+
+use sui_types::{
+    balance::Balance,
+    base_types::SuiAddress,
+    gas_coin::GAS,
+    id::{ID, UID},
+    TypeTag, MOVE_STDLIB_ADDRESS, SUI_FRAMEWORK_ADDRESS, SUI_FRAMEWORK_PACKAGE_ID,
+};
+
+use crate::{
+    abi::{Abi, AbiType, Abilities, FunctionSignature, StructAbilities, Usage},
+    move_fn,
+    ptb::MoveType,
+    MoveStruct,
+};
+
+/// Parse the type tag of a non-generic framework type
+fn framework_type(type_: &str) -> TypeTag {
+    TypeTag::from_str(type_).expect("Framework type tags are valid")
+}
+
+/// `0x2::sui::SUI`
+#[derive(Debug)]
+pub struct SUI;
+
+impl MoveType for SUI {
+    fn type_tag() -> TypeTag {
+        GAS::type_tag()
+    }
+}
+
+/// `0x2::coin::Coin<T>`
+#[derive(Debug, MoveStruct)]
+#[move_struct(package = "0x2", module = "coin")]
+pub struct Coin<T> {
+    pub id: UID,
+    pub balance: Balance,
+    #[move_struct(skip)]
+    _type: PhantomData<T>,
+}
+
+impl<T> Coin<T> {
+    pub fn value(&self) -> u64 {
+        self.balance.value()
+    }
+}
+
+impl MoveType for ID {
+    fn type_tag() -> TypeTag {
+        framework_type("0x2::object::ID")
+    }
+}
+
+/// `0x2::kiosk::Kiosk`
+#[derive(Debug, MoveStruct)]
+#[move_struct(package = "0x2", module = "kiosk")]
+pub struct Kiosk {
+    pub id: UID,
+    /// Balance of the Kiosk - all profits from sales go here.
+    pub profits: Balance,
+    /// Always point to `sender` of the transaction.
+    pub owner: SuiAddress,
+    /// Number of items stored in a Kiosk.
+    pub item_count: u32,
+    /// Whether to open the UID to public.
+    pub allow_extensions: bool,
+}
+
+/// `0x2::kiosk::KioskOwnerCap`
+#[derive(Debug, MoveStruct)]
+#[move_struct(package = "0x2", module = "kiosk")]
+pub struct KioskOwnerCap {
+    pub id: UID,
+    #[move_struct(rename = "for")]
+    pub for_: ID,
+}
+
+/// Public functions of the Sui framework modules covered here, with their number of type parameters
+const PUBLIC_FUNCTIONS: &[(&str, &str, usize)] = &[
+    ("balance", "value", 1),
+    ("balance", "supply_value", 1),
+    ("balance", "zero", 1),
+    ("balance", "join", 1),
+    ("balance", "split", 1),
+    ("balance", "withdraw_all", 1),
+    ("balance", "destroy_zero", 1),
+    ("coin", "total_supply", 1),
+    ("coin", "treasury_into_supply", 1),
+    ("coin", "supply_immut", 1),
+    ("coin", "supply_mut", 1),
+    ("coin", "value", 1),
+    ("coin", "balance", 1),
+    ("coin", "balance_mut", 1),
+    ("coin", "from_balance", 1),
+    ("coin", "into_balance", 1),
+    ("coin", "take", 1),
+    ("coin", "put", 1),
+    ("coin", "join", 1),
+    ("coin", "split", 1),
+    ("coin", "divide_into_n", 1),
+    ("coin", "zero", 1),
+    ("coin", "destroy_zero", 1),
+    ("coin", "mint", 1),
+    ("coin", "mint_balance", 1),
+    ("coin", "burn", 1),
+    ("coin", "mint_and_transfer", 1),
+    ("kiosk", "new", 0),
+    ("kiosk", "close_and_withdraw", 0),
+    ("kiosk", "set_owner", 0),
+    ("kiosk", "set_owner_custom", 0),
+    ("kiosk", "place", 1),
+    ("kiosk", "lock", 1),
+    ("kiosk", "take", 1),
+    ("kiosk", "list", 1),
+    ("kiosk", "place_and_list", 1),
+    ("kiosk", "delist", 1),
+    ("kiosk", "purchase", 1),
+    ("kiosk", "list_with_purchase_cap", 1),
+    ("kiosk", "purchase_with_cap", 1),
+    ("kiosk", "return_purchase_cap", 1),
+    ("kiosk", "withdraw", 0),
+    ("kiosk", "has_item", 0),
+    ("kiosk", "has_item_with_type", 1),
+    ("kiosk", "is_locked", 0),
+    ("kiosk", "is_listed", 0),
+    ("kiosk", "is_listed_exclusively", 0),
+    ("kiosk", "has_access", 0),
+    ("kiosk", "uid_mut_as_owner", 0),
+    ("kiosk", "set_allow_extensions", 0),
+    ("kiosk", "uid", 0),
+    ("kiosk", "uid_mut", 0),
+    ("kiosk", "owner", 0),
+    ("kiosk", "item_count", 0),
+    ("kiosk", "profits_amount", 0),
+    ("kiosk", "profits_mut", 0),
+    ("kiosk", "borrow", 1),
+    ("kiosk", "borrow_mut", 1),
+    ("kiosk", "borrow_val", 1),
+    ("kiosk", "return_val", 1),
+    ("kiosk", "kiosk_owner_cap_for", 0),
+    ("kiosk", "purchase_cap_kiosk", 1),
+    ("kiosk", "purchase_cap_item", 1),
+    ("kiosk", "purchase_cap_min_price", 1),
+    ("math", "max", 0),
+    ("math", "min", 0),
+    ("math", "diff", 0),
+    ("math", "pow", 0),
+    ("math", "sqrt", 0),
+    ("math", "sqrt_u128", 0),
+    ("math", "divide_and_round_up", 0),
+    ("pay", "keep", 1),
+    ("pay", "split", 1),
+    ("pay", "split_vec", 1),
+    ("pay", "split_and_transfer", 1),
+    ("pay", "divide_and_keep", 1),
+    ("pay", "join", 1),
+    ("pay", "join_vec", 1),
+    ("pay", "join_vec_and_transfer", 1),
+    ("transfer", "transfer", 1),
+    ("transfer", "public_transfer", 1),
+    ("transfer", "freeze_object", 1),
+    ("transfer", "public_freeze_object", 1),
+    ("transfer", "share_object", 1),
+    ("transfer", "public_share_object", 1),
+];
+
+/// Number of type parameters of a public framework function, `None` if there is no such function
+pub fn type_parameter_count(module: &str, function: &str) -> Option<usize> {
+    PUBLIC_FUNCTIONS
+        .iter()
+        .find(|(m, f, _)| *m == module && *f == function)
+        .map(|(_, _, type_parameters)| *type_parameters)
+}
+
+/// Abilities of the framework structs used here, and signatures of the functions with a descriptor
+/// below, for the PTB analysis
+pub(crate) fn register_abi(abi: &mut Abi) {
+    // None of these structs have non-phantom type parameters but `Option`
+    let phantom = |abilities, type_parameters| StructAbilities {
+        abilities,
+        phantom: vec![true; type_parameters],
+    };
+
+    // Only `copy` and `drop` are tracked: objects, having `key` and usually `store`, get
+    // `Abilities::NONE`
+    abi.add_struct(
+        SUI_FRAMEWORK_ADDRESS,
+        "coin",
+        "Coin",
+        phantom(Abilities::NONE, 1),
+    );
+    abi.add_struct(
+        SUI_FRAMEWORK_ADDRESS,
+        "balance",
+        "Balance",
+        phantom(Abilities::NONE, 1),
+    );
+    abi.add_struct(
+        SUI_FRAMEWORK_ADDRESS,
+        "sui",
+        "SUI",
+        phantom(
+            Abilities {
+                copy: false,
+                drop: true,
+            },
+            0,
+        ),
+    );
+    abi.add_struct(
+        SUI_FRAMEWORK_ADDRESS,
+        "object",
+        "UID",
+        phantom(Abilities::NONE, 0),
+    );
+    abi.add_struct(
+        SUI_FRAMEWORK_ADDRESS,
+        "object",
+        "ID",
+        phantom(Abilities::COPY_DROP, 0),
+    );
+    abi.add_struct(
+        SUI_FRAMEWORK_ADDRESS,
+        "kiosk",
+        "Kiosk",
+        phantom(Abilities::NONE, 0),
+    );
+    abi.add_struct(
+        SUI_FRAMEWORK_ADDRESS,
+        "kiosk",
+        "KioskOwnerCap",
+        phantom(Abilities::NONE, 0),
+    );
+    abi.add_struct(
+        MOVE_STDLIB_ADDRESS,
+        "string",
+        "String",
+        phantom(Abilities::COPY_DROP, 0),
+    );
+    abi.add_struct(
+        MOVE_STDLIB_ADDRESS,
+        "ascii",
+        "String",
+        phantom(Abilities::COPY_DROP, 0),
+    );
+    abi.add_struct(
+        MOVE_STDLIB_ADDRESS,
+        "option",
+        "Option",
+        StructAbilities {
+            abilities: Abilities::COPY_DROP,
+            phantom: vec![false],
+        },
+    );
+
+    let coin = || AbiType::framework("coin", "Coin", vec![AbiType::TypeParameter(0)]);
+    let sui_coin = || {
+        AbiType::framework(
+            "coin",
+            "Coin",
+            vec![AbiType::framework("sui", "SUI", vec![])],
+        )
+    };
+    let kiosk = || AbiType::framework("kiosk", "Kiosk", vec![]);
+    let kiosk_owner_cap = || AbiType::framework("kiosk", "KioskOwnerCap", vec![]);
+    let u64 = || AbiType::Primitive;
+
+    let signatures = [
+        ("coin", "value", vec![(Usage::ByRef, coin())], vec![u64()]),
+        (
+            "coin",
+            "join",
+            vec![(Usage::ByMutRef, coin()), (Usage::ByValue, coin())],
+            vec![],
+        ),
+        (
+            "coin",
+            "split",
+            vec![(Usage::ByMutRef, coin()), (Usage::ByValue, u64())],
+            vec![coin()],
+        ),
+        ("coin", "zero", vec![], vec![coin()]),
+        (
+            "coin",
+            "destroy_zero",
+            vec![(Usage::ByValue, coin())],
+            vec![],
+        ),
+        (
+            "math",
+            "diff",
+            vec![(Usage::ByValue, u64()), (Usage::ByValue, u64())],
+            vec![u64()],
+        ),
+        (
+            "math",
+            "divide_and_round_up",
+            vec![(Usage::ByValue, u64()), (Usage::ByValue, u64())],
+            vec![u64()],
+        ),
+        ("kiosk", "new", vec![], vec![kiosk(), kiosk_owner_cap()]),
+        (
+            "kiosk",
+            "close_and_withdraw",
+            vec![
+                (Usage::ByValue, kiosk()),
+                (Usage::ByValue, kiosk_owner_cap()),
+            ],
+            vec![sui_coin()],
+        ),
+        (
+            "kiosk",
+            "has_item",
+            vec![
+                (Usage::ByRef, kiosk()),
+                (Usage::ByValue, AbiType::framework("object", "ID", vec![])),
+            ],
+            vec![AbiType::Primitive],
+        ),
+        (
+            "transfer",
+            "public_transfer",
+            vec![
+                (Usage::ByValue, AbiType::TypeParameter(0)),
+                (Usage::ByValue, AbiType::Primitive),
+            ],
+            vec![],
+        ),
+    ];
+    for (module, function, parameters, returns) in signatures {
+        abi.add_function(
+            SUI_FRAMEWORK_ADDRESS,
+            module,
+            function,
+            FunctionSignature {
+                parameters,
+                returns,
+            },
+        );
+    }
+}
+
+pub mod coin {
+    use super::*;
+
+    move_fn! {
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L86-L89
+        pub fn value<T>(coin: Coin<T>) -> u64 = SUI_FRAMEWORK_PACKAGE_ID::coin::value;
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L148-L154
+        pub fn join<T>(coin: Coin<T>, other: Coin<T>) = SUI_FRAMEWORK_PACKAGE_ID::coin::join;
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L164-L170
+        pub fn split<T>(coin: Coin<T>, amount: u64) -> Coin<T> = SUI_FRAMEWORK_PACKAGE_ID::coin::split;
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L217-L221
+        pub fn zero<T>() -> Coin<T> = SUI_FRAMEWORK_PACKAGE_ID::coin::zero;
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L223-L228
+        pub fn destroy_zero<T>(coin: Coin<T>) = SUI_FRAMEWORK_PACKAGE_ID::coin::destroy_zero;
+    }
+}
+
+pub mod math {
+    use super::*;
+
+    move_fn! {
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/math.move#L25-L32
+        pub fn diff(x: u64, y: u64) -> u64 = SUI_FRAMEWORK_PACKAGE_ID::math::diff;
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/math.move#L136-L143
+        pub fn divide_and_round_up(x: u64, y: u64) -> u64 = SUI_FRAMEWORK_PACKAGE_ID::math::divide_and_round_up;
+    }
+}
+
+pub mod kiosk {
+    use super::*;
+
+    move_fn! {
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/kiosk/kiosk.move#L184C1-L200
+        pub fn new() -> (Kiosk, KioskOwnerCap) = SUI_FRAMEWORK_PACKAGE_ID::kiosk::new;
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/kiosk/kiosk.move#L202-L218
+        pub fn close_and_withdraw(kiosk: Kiosk, cap: KioskOwnerCap) -> Coin<SUI> = SUI_FRAMEWORK_PACKAGE_ID::kiosk::close_and_withdraw;
+        /// https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/kiosk/kiosk.move#L414-L417
+        pub fn has_item(kiosk: Kiosk, id: ID) -> bool = SUI_FRAMEWORK_PACKAGE_ID::kiosk::has_item;
+    }
+}
+
+pub mod transfer {
+    use super::*;
+
+    move_fn! {
+        pub fn public_transfer<T>(object: T, recipient: SuiAddress) = SUI_FRAMEWORK_PACKAGE_ID::transfer::public_transfer;
+    }
+}
@@ -0,0 +1,409 @@
+use futures::TryStreamExt;
+use sui_sdk::rpc_types::SuiObjectData;
+use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
+
+use crate::{
+    decode_gas_coin, fetch_sorted_gas_coins, gas_coins_query, limits::MAX_GAS_PAYMENT_OBJECTS,
+    owned_object_pages_stream, Error, NodeApi, Result,
+};
+
+/// Upper bound on the number of subsets explored by the fewest-objects search before falling back
+/// to the largest-first selection
+const MAX_KNAPSACK_STEPS: usize = 100_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectionStrategy {
+    /// Take the richest coins until the budget is covered
+    #[default]
+    LargestFirst,
+    /// Take the single poorest coin that covers the budget on its own
+    SmallestSufficient,
+    /// Take as few coins as possible, and among those the set overshooting the budget the least
+    FewestObjects,
+}
+
+/// Pick coins from `coins` (expected sorted largest-first, as returned by `fetch_sorted_gas_coins`)
+/// covering `budget`, never selecting any object listed in `exclude` (e.g. coins the PTB itself uses).
+pub fn select_gas_coins(
+    coins: &[(SuiObjectData, u64)],
+    budget: u64,
+    exclude: &[ObjectID],
+    strategy: SelectionStrategy,
+) -> Result<Vec<(SuiObjectData, u64)>> {
+    let mut candidates: Vec<&(SuiObjectData, u64)> = coins
+        .iter()
+        .filter(|(coin, balance)| *balance > 0 && !exclude.contains(&coin.object_id))
+        .collect();
+    candidates.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    let available: u128 = candidates.iter().map(|(_, b)| *b as u128).sum();
+    if available < budget as u128 {
+        return Err(Error::InsufficientGas { available, budget });
+    }
+
+    let selected = match strategy {
+        SelectionStrategy::LargestFirst => largest_first(&candidates, budget),
+        SelectionStrategy::SmallestSufficient => {
+            match candidates.iter().rev().find(|(_, b)| *b >= budget) {
+                Some(coin) => vec![*coin],
+                None => return Err(Error::NoSufficientCoin(budget)),
+            }
+        }
+        SelectionStrategy::FewestObjects => fewest_objects(&candidates, budget),
+    };
+
+    if selected.len() > MAX_GAS_PAYMENT_OBJECTS {
+        return Err(Error::TooManyGasObjects {
+            budget,
+            count: selected.len(),
+        });
+    }
+
+    Ok(selected.into_iter().cloned().collect())
+}
+
+/// Fetch the sender's gas coins and select a payment covering `budget`, see `select_gas_coins`.
+pub async fn select_gas_payment(
+    rpc_client: &impl NodeApi,
+    sender: &SuiAddress,
+    budget: u64,
+    exclude: &[ObjectID],
+    strategy: SelectionStrategy,
+) -> Result<Vec<ObjectRef>> {
+    let gas_coins = fetch_sorted_gas_coins(rpc_client, sender).await?;
+    let selected = select_gas_coins(&gas_coins, budget, exclude, strategy)?;
+
+    Ok(selected.iter().map(|(coin, _)| coin.object_ref()).collect())
+}
+
+/// Outcome of `select_gas_payment_early`
+#[derive(Debug, Clone)]
+pub struct EarlySelection {
+    /// Selected coins, sorted by decreasing balance
+    pub coins: Vec<(SuiObjectData, u64)>,
+    /// Number of owned-object pages requested before the target was covered
+    pub pages_read: usize,
+}
+
+impl EarlySelection {
+    pub fn gas_payment(&self) -> Vec<ObjectRef> {
+        self.coins
+            .iter()
+            .map(|(coin, _)| coin.object_ref())
+            .collect()
+    }
+}
+
+/// Page through the sender's gas coins only until they cover `budget` plus `amount` (the balance
+/// the PTB itself takes from `Argument::GasCoin`), then select the fewest of the coins seen so far.
+///
+/// Unlike `select_gas_payment` this does not look at every owned coin, so the selection is only
+/// optimal among the pages read.
+pub async fn select_gas_payment_early(
+    rpc_client: &impl NodeApi,
+    sender: &SuiAddress,
+    budget: u64,
+    amount: u64,
+    exclude: &[ObjectID],
+    page_size: Option<usize>,
+) -> Result<EarlySelection> {
+    let target = budget
+        .checked_add(amount)
+        .ok_or(Error::GasTargetOverflow { budget, amount })?;
+
+    let mut pages = Box::pin(owned_object_pages_stream(
+        rpc_client,
+        *sender,
+        gas_coins_query(),
+        page_size,
+    ));
+    let mut seen: Vec<(SuiObjectData, u64)> = vec![];
+    let mut pages_read = 0;
+
+    while let Some(page) = pages.try_next().await? {
+        pages_read += 1;
+        for object in page {
+            if exclude.contains(&object.object_id) {
+                continue;
+            }
+            let balance = decode_gas_coin(&object)?;
+            if balance > 0 {
+                seen.push((object, balance));
+            }
+        }
+
+        // Only the richest coins allowed in a gas payment can ever be selected, smaller ones
+        // can be dropped right away
+        seen.sort_by(|(_, a), (_, b)| b.cmp(a));
+        seen.truncate(MAX_GAS_PAYMENT_OBJECTS);
+
+        let covered: u128 = seen.iter().map(|(_, b)| *b as u128).sum();
+        if covered >= target as u128 {
+            let coins = select_gas_coins(&seen, target, exclude, SelectionStrategy::LargestFirst)?;
+            return Ok(EarlySelection { coins, pages_read });
+        }
+    }
+
+    Err(Error::GasTargetNotCovered {
+        owner: *sender,
+        budget,
+        amount,
+        pages_read,
+    })
+}
+
+fn largest_first<'a>(
+    candidates: &[&'a (SuiObjectData, u64)],
+    budget: u64,
+) -> Vec<&'a (SuiObjectData, u64)> {
+    let mut total = 0u128;
+    let mut selected = vec![];
+    for coin in candidates {
+        if total >= budget as u128 && !selected.is_empty() {
+            break;
+        }
+        total += coin.1 as u128;
+        selected.push(*coin);
+    }
+    selected
+}
+
+fn fewest_objects<'a>(
+    candidates: &[&'a (SuiObjectData, u64)],
+    budget: u64,
+) -> Vec<&'a (SuiObjectData, u64)> {
+    // Largest-first is optimal in object count, so it gives us the target size `k` and an initial
+    // upper bound on the overshoot. We then look for a cheaper `k`-subset.
+    let greedy = largest_first(candidates, budget);
+    let k = greedy.len();
+    let balances: Vec<u128> = candidates.iter().map(|(_, b)| *b as u128).collect();
+
+    let mut best_total: u128 = greedy.iter().map(|(_, b)| *b as u128).sum();
+    let mut best: Vec<usize> = (0..k).collect();
+    let mut current = Vec::with_capacity(k);
+    let mut steps = 0;
+
+    search(
+        &balances,
+        budget as u128,
+        k,
+        0,
+        0,
+        &mut current,
+        &mut best,
+        &mut best_total,
+        &mut steps,
+    );
+
+    best.into_iter().map(|i| candidates[i]).collect()
+}
+
+#[allow(clippy::too_many_arguments)]
+fn search(
+    balances: &[u128],
+    budget: u128,
+    k: usize,
+    start: usize,
+    total: u128,
+    current: &mut Vec<usize>,
+    best: &mut Vec<usize>,
+    best_total: &mut u128,
+    steps: &mut usize,
+) {
+    *steps += 1;
+    if *steps > MAX_KNAPSACK_STEPS {
+        return;
+    }
+
+    if current.len() == k {
+        if total >= budget && total < *best_total {
+            *best_total = total;
+            *best = current.clone();
+        }
+        return;
+    }
+
+    let remaining = k - current.len();
+    for i in start..balances.len() {
+        if balances.len() - i < remaining {
+            break;
+        }
+        // Balances are sorted descending, so the best we can still reach from here is taking the
+        // next `remaining` coins. If that falls short of the budget, so will every later start.
+        let reachable: u128 = total + balances[i..i + remaining].iter().sum::<u128>();
+        if reachable < budget {
+            break;
+        }
+        // Already worse than the best subset found, skip to a smaller coin
+        if total + balances[i] >= *best_total {
+            continue;
+        }
+        current.push(i);
+        search(
+            balances,
+            budget,
+            k,
+            i + 1,
+            total + balances[i],
+            current,
+            best,
+            best_total,
+            steps,
+        );
+        current.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+
+    use super::*;
+    use crate::{
+        fake::{object_id, sui_coin_object},
+        FakeNode,
+    };
+
+    /// Coins with the given balances, sorted largest-first as `fetch_sorted_gas_coins` returns them
+    fn coins(balances: &[u64]) -> Vec<(SuiObjectData, u64)> {
+        let mut coins: Vec<_> = balances
+            .iter()
+            .zip(1..)
+            .map(|(balance, index)| (sui_coin_object(object_id(index), *balance), *balance))
+            .collect();
+        coins.sort_by(|(_, a), (_, b)| b.cmp(a));
+        coins
+    }
+
+    fn balances(selected: &[(SuiObjectData, u64)]) -> Vec<u64> {
+        selected.iter().map(|(_, balance)| *balance).collect()
+    }
+
+    fn fewest(balances: &[u64], budget: u64) -> Result<Vec<(SuiObjectData, u64)>> {
+        select_gas_coins(
+            &coins(balances),
+            budget,
+            &[],
+            SelectionStrategy::FewestObjects,
+        )
+    }
+
+    #[test]
+    fn fewest_objects_minimizes_the_overshoot() {
+        let available = [10, 7, 5, 4, 1];
+        assert_eq!(balances(&fewest(&available, 9).unwrap()), vec![10]);
+        assert_eq!(balances(&fewest(&available, 12).unwrap()), vec![7, 5]);
+        assert_eq!(balances(&fewest(&available, 15).unwrap()), vec![10, 5]);
+        assert_eq!(balances(&fewest(&available, 21).unwrap()), vec![10, 7, 4]);
+        assert_eq!(
+            balances(&fewest(&available, 27).unwrap()),
+            vec![10, 7, 5, 4, 1]
+        );
+    }
+
+    #[test]
+    fn strategies_differ_on_the_same_coins() {
+        let coins = coins(&[10, 7, 5, 4, 1]);
+        let select = |strategy| balances(&select_gas_coins(&coins, 6, &[], strategy).unwrap());
+
+        assert_eq!(select(SelectionStrategy::LargestFirst), vec![10]);
+        assert_eq!(select(SelectionStrategy::SmallestSufficient), vec![7]);
+        assert_eq!(select(SelectionStrategy::FewestObjects), vec![7]);
+    }
+
+    #[test]
+    fn excluded_and_empty_coins_are_never_selected() {
+        let coins = coins(&[10, 7, 0, 5]);
+        let selected =
+            select_gas_coins(&coins, 5, &[object_id(1)], SelectionStrategy::FewestObjects).unwrap();
+        assert_eq!(balances(&selected), vec![5]);
+    }
+
+    #[test]
+    fn selection_errors_are_typed() {
+        let coins = coins(&[10, 7]);
+        assert!(matches!(
+            select_gas_coins(&coins, 18, &[], SelectionStrategy::FewestObjects),
+            Err(Error::InsufficientGas {
+                available: 17,
+                budget: 18
+            })
+        ));
+        assert!(matches!(
+            select_gas_coins(&coins, 11, &[], SelectionStrategy::SmallestSufficient),
+            Err(Error::NoSufficientCoin(11))
+        ));
+
+        let dust = coins(&[1; MAX_GAS_PAYMENT_OBJECTS + 1]);
+        assert!(matches!(
+            select_gas_coins(&dust, 300, &[], SelectionStrategy::LargestFirst),
+            Err(Error::InsufficientGas { .. })
+        ));
+        assert!(matches!(
+            select_gas_coins(
+                &dust,
+                MAX_GAS_PAYMENT_OBJECTS as u64 + 1,
+                &[],
+                SelectionStrategy::LargestFirst
+            ),
+            Err(Error::TooManyGasObjects { count, .. }) if count == MAX_GAS_PAYMENT_OBJECTS + 1
+        ));
+    }
+
+    proptest! {
+        /// Branch and bound against an exhaustive search over every subset
+        #[test]
+        fn fewest_objects_is_optimal(available in vec(1..1_000u64, 1..10), budget in 1..3_000u64) {
+            let total: u64 = available.iter().sum();
+            prop_assume!(total >= budget);
+
+            let mut best: Option<(u32, u64)> = None;
+            for mask in 1..1u32 << available.len() {
+                let sum: u64 = (0..available.len())
+                    .filter(|i| mask & (1 << i) != 0)
+                    .map(|i| available[i])
+                    .sum();
+                let candidate = (mask.count_ones(), sum);
+                if sum >= budget && best.is_none_or(|best| candidate < best) {
+                    best = Some(candidate);
+                }
+            }
+
+            let selected = balances(&fewest(&available, budget).unwrap());
+            let sum: u64 = selected.iter().sum();
+            prop_assert_eq!(Some((selected.len() as u32, sum)), best);
+        }
+    }
+
+    #[tokio::test]
+    async fn early_selection_stops_once_covered() {
+        let owner = SuiAddress::ZERO;
+        let objects = [5, 40, 30, 1, 100]
+            .iter()
+            .zip(1..)
+            .map(|(balance, index)| sui_coin_object(object_id(index), *balance))
+            .collect();
+        let node = FakeNode::new().with_owned_objects(owner, objects);
+
+        let selection = select_gas_payment_early(&node, &owner, 50, 10, &[], Some(2))
+            .await
+            .unwrap();
+        assert_eq!(selection.pages_read, 2);
+        assert_eq!(balances(&selection.coins), vec![40, 30]);
+
+        let selection = select_gas_payment_early(&node, &owner, 50, 10, &[object_id(2)], Some(2))
+            .await
+            .unwrap();
+        assert_eq!(selection.pages_read, 3);
+        assert_eq!(balances(&selection.coins), vec![100]);
+
+        assert!(matches!(
+            select_gas_payment_early(&node, &owner, 200, 0, &[], Some(2)).await,
+            Err(Error::GasTargetNotCovered { pages_read: 3, .. })
+        ));
+        assert!(matches!(
+            select_gas_payment_early(&node, &owner, u64::MAX, 1, &[], Some(2)).await,
+            Err(Error::GasTargetOverflow { .. })
+        ));
+    }
+}
@@ -0,0 +1,415 @@
+//! Rewrite of PTBs passing gas payment coins as inputs, which the protocol rejects (see
+//! `Lint::GasCoinAsInput`), into PTBs using `Argument::GasCoin` instead.
+
+use anyhow::{bail, ensure};
+use sui_types::{
+    base_types::ObjectRef,
+    transaction::{Argument, CallArg, Command, ObjectArg, ProgrammableTransaction},
+};
+
+use crate::{
+    abi::Abi,
+    labels::command_arguments,
+    limits::MAX_COMMANDS,
+    lint::{check_moves, Lint},
+};
+
+/// What the rewritten PTB gets in place of the gas payment coins
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GasCoinUse {
+    /// `Argument::GasCoin` itself, holding the whole balance. Only `TransferObjects` may take it
+    /// by value.
+    Whole,
+    /// A coin of `amount` split off the gas coin by a leading `SplitCoins`, usually the balance
+    /// minus the gas budget. It can be used like any other coin.
+    Split { amount: u64 },
+}
+
+/// Replace every input of `pt` referring to a `gas_payment` coin by `gas_coin_use`, renumbering
+/// the remaining inputs, and the results when a `SplitCoins` is inserted. A PTB without such
+/// inputs is returned as is.
+///
+/// All gas payment coins become the same coin, so this fails rather than returning an invalid PTB
+/// when a command would get that coin twice (e.g. merging two gas payment coins together), or
+/// when the framework functions known to `Abi::framework` would take `Argument::GasCoin` by value.
+pub fn rewrite_gas_coin(
+    pt: &ProgrammableTransaction,
+    gas_payment: &[ObjectRef],
+    gas_coin_use: GasCoinUse,
+) -> anyhow::Result<ProgrammableTransaction> {
+    rewrite_gas_coin_with_abi(pt, gas_payment, gas_coin_use, &Abi::framework())
+}
+
+/// Same as `rewrite_gas_coin`, checking the calls to the functions of `abi`. Calls to other
+/// functions are assumed to take the gas coin by reference.
+pub fn rewrite_gas_coin_with_abi(
+    pt: &ProgrammableTransaction,
+    gas_payment: &[ObjectRef],
+    gas_coin_use: GasCoinUse,
+    abi: &Abi,
+) -> anyhow::Result<ProgrammableTransaction> {
+    let is_gas_coin = |call_arg: &CallArg| match call_arg {
+        CallArg::Object(ObjectArg::ImmOrOwnedObject((object_id, _, _))) => {
+            gas_payment.iter().any(|(id, _, _)| id == object_id)
+        }
+        _ => false,
+    };
+    if !pt.inputs.iter().any(is_gas_coin) {
+        return Ok(pt.clone());
+    }
+
+    // New index of each input, `None` for the gas payment coins
+    let mut inputs = vec![];
+    let mut input_indices = vec![];
+    for call_arg in &pt.inputs {
+        if is_gas_coin(call_arg) {
+            input_indices.push(None);
+        } else {
+            input_indices.push(Some(inputs.len() as u16));
+            inputs.push(call_arg.clone());
+        }
+    }
+
+    let mut commands = vec![];
+    let (replacement, result_offset) = match gas_coin_use {
+        GasCoinUse::Whole => (Argument::GasCoin, 0),
+        GasCoinUse::Split { amount } => {
+            ensure!(
+                pt.commands.len() < MAX_COMMANDS,
+                "Cannot insert a `SplitCoins` into a PTB of {} commands",
+                pt.commands.len()
+            );
+            let amount_arg = Argument::Input(inputs.len() as u16);
+            inputs.push(CallArg::Pure(bcs::to_bytes(&amount)?));
+            commands.push(Command::SplitCoins(Argument::GasCoin, vec![amount_arg]));
+            (Argument::NestedResult(0, 0), 1)
+        }
+    };
+
+    let rewrite = |argument: Argument| -> anyhow::Result<Argument> {
+        Ok(match argument {
+            Argument::GasCoin => Argument::GasCoin,
+            Argument::Input(input) => match input_indices.get(input as usize) {
+                Some(Some(new_input)) => Argument::Input(*new_input),
+                Some(None) => replacement,
+                None => bail!("Argument refers to missing input {}", input),
+            },
+            Argument::Result(command) => Argument::Result(command + result_offset),
+            Argument::NestedResult(command, result) => {
+                Argument::NestedResult(command + result_offset, result)
+            }
+        })
+    };
+    for command in &pt.commands {
+        commands.push(map_arguments(command, &rewrite)?);
+    }
+
+    let rewritten = ProgrammableTransaction { inputs, commands };
+    check_gas_coin_uses(&rewritten, replacement, abi)?;
+    Ok(rewritten)
+}
+
+/// Fail if `replacement`, standing for every gas payment coin, ends up used in a way the
+/// distinct coins could be but the single one cannot
+fn check_gas_coin_uses(
+    pt: &ProgrammableTransaction,
+    replacement: Argument,
+    abi: &Abi,
+) -> anyhow::Result<()> {
+    for (index, command) in pt.commands.iter().enumerate() {
+        let uses = command_arguments(command)
+            .into_iter()
+            .filter(|argument| *argument == replacement)
+            .count();
+        ensure!(
+            uses <= 1,
+            "Command {} would get the gas coin {} times, as all gas payment coins become the same \
+             coin",
+            index,
+            uses
+        );
+    }
+
+    for lint in check_moves(pt, abi) {
+        match lint {
+            Lint::GasCoinByValue { command } if replacement == Argument::GasCoin => bail!(
+                "Command {} would take the gas coin by value, use `GasCoinUse::Split` instead",
+                command
+            ),
+            Lint::UseAfterMove {
+                command, argument, ..
+            } if argument == replacement => bail!(
+                "Command {} would use the gas coin after it is moved, as all gas payment coins \
+                 become the same coin",
+                command
+            ),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// `command` with each of its arguments passed through `f`
+fn map_arguments(
+    command: &Command,
+    f: impl Fn(Argument) -> anyhow::Result<Argument>,
+) -> anyhow::Result<Command> {
+    let map_all = |arguments: &[Argument]| {
+        arguments
+            .iter()
+            .map(|argument| f(*argument))
+            .collect::<anyhow::Result<Vec<_>>>()
+    };
+
+    Ok(match command {
+        Command::MoveCall(call) => {
+            let mut call = call.clone();
+            call.arguments = map_all(&call.arguments)?;
+            Command::MoveCall(call)
+        }
+        Command::TransferObjects(objects, recipient) => {
+            Command::TransferObjects(map_all(objects)?, f(*recipient)?)
+        }
+        Command::SplitCoins(coin, amounts) => Command::SplitCoins(f(*coin)?, map_all(amounts)?),
+        Command::MergeCoins(destination, sources) => {
+            Command::MergeCoins(f(*destination)?, map_all(sources)?)
+        }
+        Command::Publish(modules, dependencies) => {
+            Command::Publish(modules.clone(), dependencies.clone())
+        }
+        Command::MakeMoveVec(type_tag, elements) => {
+            Command::MakeMoveVec(type_tag.clone(), map_all(elements)?)
+        }
+        Command::Upgrade(modules, dependencies, package, ticket) => {
+            Command::Upgrade(modules.clone(), dependencies.clone(), *package, f(*ticket)?)
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*};
+    use sui_types::{
+        base_types::{ObjectDigest, ObjectID, SequenceNumber},
+        transaction::ProgrammableMoveCall,
+        Identifier,
+    };
+
+    use super::*;
+
+    fn gas_coin(index: u8) -> ObjectRef {
+        (
+            ObjectID::new([index + 1; 32]),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::new([0; 32]),
+        )
+    }
+
+    fn gas_input(index: u8) -> CallArg {
+        CallArg::Object(ObjectArg::ImmOrOwnedObject(gas_coin(index)))
+    }
+
+    /// Call to a function unknown to `Abi::framework`, so that no argument counts as moved
+    fn call(arguments: Vec<Argument>) -> Command {
+        Command::MoveCall(Box::new(ProgrammableMoveCall {
+            package: ObjectID::new([0xab; 32]),
+            module: Identifier::new("m").unwrap(),
+            function: Identifier::new("f").unwrap(),
+            type_arguments: vec![],
+            arguments,
+        }))
+    }
+
+    /// A PTB over `gas_count` gas payment coins: each input is a gas payment coin or a pure value,
+    /// and each command a call taking inputs, earlier results and the gas coin, at most one gas
+    /// payment coin per command so that the rewrite is valid
+    fn arb_pt() -> impl Strategy<Value = (ProgrammableTransaction, Vec<ObjectRef>)> {
+        (
+            1..4u8,
+            vec(prop::option::of(0..4u8), 1..8),
+            vec(vec((0..4u8, any::<u16>(), 0..3u16), 0..5), 1..10),
+        )
+            .prop_map(|(gas_count, inputs, commands)| {
+                let gas_payment: Vec<_> = (0..gas_count).map(gas_coin).collect();
+                let inputs: Vec<CallArg> = inputs
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, gas)| match gas {
+                        Some(coin) => gas_input(coin % gas_count),
+                        None => CallArg::Pure(vec![index as u8]),
+                    })
+                    .collect();
+                let is_gas = |argument: &Argument| match argument {
+                    Argument::GasCoin => true,
+                    Argument::Input(input) => matches!(inputs[*input as usize], CallArg::Object(_)),
+                    _ => false,
+                };
+
+                let commands = commands
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, arguments)| {
+                        let index = index as u16;
+                        let mut gas_used = false;
+                        let arguments = arguments
+                            .into_iter()
+                            .map(|(kind, a, b)| match kind {
+                                0 => Argument::Input(a % inputs.len() as u16),
+                                1 if index > 0 => Argument::Result(a % index),
+                                2 if index > 0 => Argument::NestedResult(a % index, b),
+                                _ => Argument::GasCoin,
+                            })
+                            .filter(|argument| {
+                                if !is_gas(argument) {
+                                    return true;
+                                }
+                                !std::mem::replace(&mut gas_used, true)
+                            })
+                            .collect();
+                        call(arguments)
+                    })
+                    .collect();
+
+                (ProgrammableTransaction { inputs, commands }, gas_payment)
+            })
+    }
+
+    /// What the rewrite should turn `argument` into, renumbering inputs by counting the gas payment
+    /// coins before them
+    fn expected_argument(
+        pt: &ProgrammableTransaction,
+        argument: Argument,
+        replacement: Argument,
+        result_offset: u16,
+    ) -> Argument {
+        match argument {
+            Argument::GasCoin => Argument::GasCoin,
+            Argument::Input(input) => match pt.inputs[input as usize] {
+                CallArg::Object(_) => replacement,
+                CallArg::Pure(_) => {
+                    let gas_before = pt.inputs[..input as usize]
+                        .iter()
+                        .filter(|call_arg| matches!(call_arg, CallArg::Object(_)))
+                        .count();
+                    Argument::Input(input - gas_before as u16)
+                }
+            },
+            Argument::Result(command) => Argument::Result(command + result_offset),
+            Argument::NestedResult(command, result) => {
+                Argument::NestedResult(command + result_offset, result)
+            }
+        }
+    }
+
+    fn arguments(command: &Command) -> &[Argument] {
+        match command {
+            Command::MoveCall(call) => &call.arguments,
+            _ => panic!("Expected a call, got {:?}", command),
+        }
+    }
+
+    fn has_gas_input(pt: &ProgrammableTransaction) -> bool {
+        pt.inputs
+            .iter()
+            .any(|call_arg| matches!(call_arg, CallArg::Object(_)))
+    }
+
+    proptest! {
+        #[test]
+        fn split_inserts_split_coins_and_renumbers((pt, gas_payment) in arb_pt(), amount: u64) {
+            let rewritten =
+                rewrite_gas_coin(&pt, &gas_payment, GasCoinUse::Split { amount }).unwrap();
+            if !has_gas_input(&pt) {
+                prop_assert_eq!(rewritten, pt);
+                return Ok(());
+            }
+
+            let pure_inputs: Vec<_> = pt
+                .inputs
+                .iter()
+                .filter(|call_arg| matches!(call_arg, CallArg::Pure(_)))
+                .cloned()
+                .collect();
+            prop_assert_eq!(&rewritten.inputs[..pure_inputs.len()], &pure_inputs[..]);
+            prop_assert_eq!(rewritten.inputs.len(), pure_inputs.len() + 1);
+            prop_assert_eq!(
+                rewritten.inputs.last().unwrap(),
+                &CallArg::Pure(bcs::to_bytes(&amount).unwrap())
+            );
+
+            prop_assert_eq!(rewritten.commands.len(), pt.commands.len() + 1);
+            prop_assert_eq!(
+                &rewritten.commands[0],
+                &Command::SplitCoins(
+                    Argument::GasCoin,
+                    vec![Argument::Input(pure_inputs.len() as u16)]
+                )
+            );
+            for (original, command) in pt.commands.iter().zip(&rewritten.commands[1..]) {
+                let expected: Vec<_> = arguments(original)
+                    .iter()
+                    .map(|argument| {
+                        expected_argument(&pt, *argument, Argument::NestedResult(0, 0), 1)
+                    })
+                    .collect();
+                prop_assert_eq!(arguments(command), &expected[..]);
+            }
+        }
+
+        #[test]
+        fn whole_replaces_inputs_in_place((pt, gas_payment) in arb_pt()) {
+            let rewritten = rewrite_gas_coin(&pt, &gas_payment, GasCoinUse::Whole).unwrap();
+            if !has_gas_input(&pt) {
+                prop_assert_eq!(rewritten, pt);
+                return Ok(());
+            }
+
+            prop_assert!(!has_gas_input(&rewritten));
+            prop_assert_eq!(rewritten.commands.len(), pt.commands.len());
+            for (original, command) in pt.commands.iter().zip(&rewritten.commands) {
+                let expected: Vec<_> = arguments(original)
+                    .iter()
+                    .map(|argument| expected_argument(&pt, *argument, Argument::GasCoin, 0))
+                    .collect();
+                prop_assert_eq!(arguments(command), &expected[..]);
+            }
+        }
+    }
+
+    #[test]
+    fn merging_gas_payment_coins_together_fails() {
+        let pt = ProgrammableTransaction {
+            inputs: vec![gas_input(0), gas_input(1)],
+            commands: vec![Command::MergeCoins(
+                Argument::Input(0),
+                vec![Argument::Input(1)],
+            )],
+        };
+        let gas_payment = [gas_coin(0), gas_coin(1)];
+
+        assert!(rewrite_gas_coin(&pt, &gas_payment, GasCoinUse::Whole).is_err());
+        assert!(rewrite_gas_coin(&pt, &gas_payment, GasCoinUse::Split { amount: 1 }).is_err());
+    }
+
+    #[test]
+    fn whole_gas_coin_taken_by_value_fails() {
+        // `coin::join(&mut Coin, Coin)` takes its second argument by value
+        let pt = ProgrammableTransaction {
+            inputs: vec![CallArg::Pure(vec![0; 8]), gas_input(0)],
+            commands: vec![
+                Command::SplitCoins(Argument::GasCoin, vec![Argument::Input(0)]),
+                Command::MoveCall(Box::new(ProgrammableMoveCall {
+                    package: sui_types::SUI_FRAMEWORK_PACKAGE_ID,
+                    module: Identifier::new("coin").unwrap(),
+                    function: Identifier::new("join").unwrap(),
+                    type_arguments: vec![sui_types::gas_coin::GAS::type_tag()],
+                    arguments: vec![Argument::NestedResult(0, 0), Argument::Input(1)],
+                })),
+            ],
+        };
+        let gas_payment = [gas_coin(0)];
+
+        assert!(rewrite_gas_coin(&pt, &gas_payment, GasCoinUse::Whole).is_err());
+        assert!(rewrite_gas_coin(&pt, &gas_payment, GasCoinUse::Split { amount: 1 }).is_ok());
+    }
+}
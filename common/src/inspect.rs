@@ -0,0 +1,185 @@
+//! Typed access to the results of `dev_inspect_transaction_block`, looked up by the handles
+//! `TypedPtb` returned when the commands were added rather than by position.
+
+use anyhow::{anyhow, bail, ensure};
+use serde::de::DeserializeOwned;
+use sui_sdk::rpc_types::{DevInspectResults, SuiExecutionResult, SuiTypeTag};
+use sui_types::{transaction::Argument, TypeTag};
+
+use crate::{MoveType, Res};
+
+/// Results of a successful dev-inspect run
+#[derive(Debug, Clone)]
+pub struct DevInspectOutcome {
+    results: Vec<SuiExecutionResult>,
+}
+
+impl DevInspectOutcome {
+    /// Fails if the transaction aborted, with the error reported by the node
+    pub fn new(response: DevInspectResults) -> anyhow::Result<Self> {
+        if let Some(e) = response.error {
+            bail!("Transaction failed: {}", e);
+        }
+        let results = response
+            .results
+            .ok_or_else(|| anyhow!("Dev-inspect returned no results"))?;
+
+        Ok(Self { results })
+    }
+
+    /// Number of commands that were executed
+    pub fn command_count(&self) -> usize {
+        self.results.len()
+    }
+
+    /// Decode the value behind `handle`, after checking its Move type is `T`'s.
+    pub fn get<T: MoveType + DeserializeOwned>(&self, handle: Res<T>) -> anyhow::Result<T> {
+        let (bytes, sui_type_tag) = self.return_value(handle.argument())?;
+
+        let expected = T::type_tag();
+        let actual: TypeTag = sui_type_tag.clone().try_into()?;
+        ensure!(
+            actual == expected,
+            "{:?} is a {}, not a {}",
+            handle.argument(),
+            actual,
+            expected
+        );
+
+        Ok(bcs::from_bytes(bytes)?)
+    }
+
+    fn return_value(&self, argument: Argument) -> anyhow::Result<&(Vec<u8>, SuiTypeTag)> {
+        let (command, index) = match argument {
+            Argument::Result(command) => (command, 0),
+            Argument::NestedResult(command, index) => (command, index),
+            Argument::GasCoin | Argument::Input(_) => {
+                bail!("{:?} is not the result of a command", argument)
+            }
+        };
+
+        let result = self.results.get(command as usize).ok_or_else(|| {
+            anyhow!(
+                "No result for command {}, only {} commands were executed",
+                command,
+                self.results.len()
+            )
+        })?;
+        result.return_values.get(index as usize).ok_or_else(|| {
+            anyhow!(
+                "Command {} returned {} values, no value at index {}",
+                command,
+                result.return_values.len(),
+                index
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake::{dev_inspect_failure, dev_inspect_results};
+
+    fn value<T: MoveType + serde::Serialize>(value: T) -> (Vec<u8>, TypeTag) {
+        (bcs::to_bytes(&value).unwrap(), T::type_tag())
+    }
+
+    /// A `u64`, nothing, a `(bool, u64)` tuple, then values whose bytes do not match their type
+    fn outcome() -> DevInspectOutcome {
+        let response = dev_inspect_results(vec![
+            vec![value(7u64)],
+            vec![],
+            vec![value(true), value(9u64)],
+            vec![
+                (vec![1, 2], TypeTag::U64),
+                (bcs::to_bytes(&(7u64, 0u8)).unwrap(), TypeTag::U64),
+            ],
+        ]);
+        DevInspectOutcome::new(response).unwrap()
+    }
+
+    fn handle<T>(argument: Argument) -> Res<T> {
+        Res::unchecked(argument)
+    }
+
+    #[test]
+    fn results_are_looked_up_by_handle() {
+        let outcome = outcome();
+
+        assert_eq!(outcome.command_count(), 4);
+        assert_eq!(outcome.get(handle::<u64>(Argument::Result(0))).unwrap(), 7);
+        assert!(outcome
+            .get(handle::<bool>(Argument::NestedResult(2, 0)))
+            .unwrap());
+        assert_eq!(
+            outcome
+                .get(handle::<u64>(Argument::NestedResult(2, 1)))
+                .unwrap(),
+            9
+        );
+        // A result stands for the first value of its command
+        assert!(outcome.get(handle::<bool>(Argument::Result(2))).unwrap());
+        assert_eq!(
+            outcome
+                .get(handle::<u64>(Argument::NestedResult(0, 0)))
+                .unwrap(),
+            7
+        );
+    }
+
+    #[test]
+    fn values_of_another_type_are_rejected() {
+        let outcome = outcome();
+
+        let e = outcome
+            .get(handle::<bool>(Argument::Result(0)))
+            .unwrap_err();
+        assert_eq!(e.to_string(), "Result(0) is a u64, not a bool");
+        assert!(outcome
+            .get(handle::<u32>(Argument::NestedResult(2, 1)))
+            .is_err());
+    }
+
+    #[test]
+    fn missing_results_are_rejected() {
+        let outcome = outcome();
+
+        let e = outcome.get(handle::<u64>(Argument::Result(4))).unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "No result for command 4, only 4 commands were executed"
+        );
+        let e = outcome
+            .get(handle::<u64>(Argument::NestedResult(2, 2)))
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Command 2 returned 2 values, no value at index 2"
+        );
+        assert!(outcome.get(handle::<u64>(Argument::Result(1))).is_err());
+
+        for argument in [Argument::GasCoin, Argument::Input(0)] {
+            assert!(outcome.get(handle::<u64>(argument)).is_err());
+        }
+    }
+
+    #[test]
+    fn undecodable_values_are_rejected() {
+        let outcome = outcome();
+
+        // Too short, then with a trailing byte
+        assert!(outcome.get(handle::<u64>(Argument::Result(3))).is_err());
+        assert!(outcome
+            .get(handle::<u64>(Argument::NestedResult(3, 1)))
+            .is_err());
+    }
+
+    #[test]
+    fn aborted_transactions_have_no_outcome() {
+        let response = dev_inspect_failure("MoveAbort(MoveLocation { .. }, 0) in command 2");
+
+        let e = DevInspectOutcome::new(response).unwrap_err();
+        assert!(e.to_string().contains("MoveAbort"));
+    }
+}
@@ -0,0 +1,100 @@
+//! Names and types of the inputs and results of a PTB, for the diagrams and disassembly.
+
+use std::collections::HashMap;
+
+use sui_types::{
+    transaction::{Argument, Command},
+    TypeTag,
+};
+
+use crate::{MoveType, Res};
+
+#[derive(Debug, Clone)]
+pub(crate) struct Label {
+    pub name: String,
+    pub type_tag: Option<TypeTag>,
+}
+
+/// Names, and Move types when known, of PTB arguments. A name given to `Argument::Result(i)`
+/// names command `i`.
+#[derive(Debug, Clone, Default)]
+pub struct Labels {
+    arguments: HashMap<Argument, Label>,
+}
+
+impl Labels {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Label a handle with `name` and the Move type it carries
+    pub fn name<T: MoveType>(&mut self, handle: Res<T>, name: impl Into<String>) -> &mut Self {
+        self.arguments.insert(
+            handle.argument(),
+            Label {
+                name: name.into(),
+                type_tag: Some(T::type_tag()),
+            },
+        );
+        self
+    }
+
+    /// Label a raw argument, without a type
+    pub fn name_argument(&mut self, argument: Argument, name: impl Into<String>) -> &mut Self {
+        self.arguments.insert(
+            argument,
+            Label {
+                name: name.into(),
+                type_tag: None,
+            },
+        );
+        self
+    }
+
+    pub(crate) fn get(&self, argument: Argument) -> Option<&Label> {
+        self.arguments.get(&argument).or(match argument {
+            // A single result may have been labelled through either form
+            Argument::NestedResult(command, 0) => self.arguments.get(&Argument::Result(command)),
+            Argument::Result(command) => self.arguments.get(&Argument::NestedResult(command, 0)),
+            _ => None,
+        })
+    }
+}
+
+/// Arguments of `command`, in order
+pub(crate) fn command_arguments(command: &Command) -> Vec<Argument> {
+    match command {
+        Command::MoveCall(call) => call.arguments.clone(),
+        Command::TransferObjects(objects, recipient) => {
+            objects.iter().copied().chain([*recipient]).collect()
+        }
+        Command::SplitCoins(coin, amounts) => [*coin].into_iter().chain(amounts.clone()).collect(),
+        Command::MergeCoins(destination, sources) => {
+            [*destination].into_iter().chain(sources.clone()).collect()
+        }
+        Command::Publish(..) => vec![],
+        Command::MakeMoveVec(_, elements) => elements.clone(),
+        Command::Upgrade(_, _, _, ticket) => vec![*ticket],
+    }
+}
+
+/// `0x2::coin::Coin<0x2::sui::SUI>` as `Coin<SUI>`
+pub(crate) fn short_type(type_tag: &TypeTag) -> String {
+    match type_tag {
+        TypeTag::Struct(struct_tag) if struct_tag.type_params.is_empty() => {
+            struct_tag.name.to_string()
+        }
+        TypeTag::Struct(struct_tag) => format!(
+            "{}<{}>",
+            struct_tag.name,
+            struct_tag
+                .type_params
+                .iter()
+                .map(short_type)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        TypeTag::Vector(inner) => format!("vector<{}>", short_type(inner)),
+        type_tag => type_tag.to_string(),
+    }
+}
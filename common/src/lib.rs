@@ -1,58 +1,154 @@
-use sui_sdk::{
-    rpc_types::{SuiObjectData, SuiObjectDataFilter, SuiObjectDataOptions, SuiObjectResponseQuery},
-    SuiClient,
+// Lets `#[derive(MoveStruct)]` refer to `::common` from within this crate too
+extern crate self as common;
+
+pub mod abi;
+pub mod budget;
+pub mod coins;
+pub mod config;
+pub mod diagram;
+pub mod disasm;
+pub mod error;
+#[cfg(any(test, feature = "fake"))]
+pub mod fake;
+pub mod fan_out;
+pub mod fixtures;
+pub mod framework;
+pub mod gas;
+pub mod gas_rewrite;
+pub mod inspect;
+pub mod labels;
+pub mod limits;
+pub mod lint;
+pub mod merge;
+pub mod min_budget;
+pub mod move_fn;
+pub mod node;
+pub mod pagination;
+pub mod pool;
+pub mod ptb;
+pub mod sweep;
+
+pub use abi::Abi;
+pub use budget::{
+    estimate_gas_budget, estimate_gas_budget_until_stable, estimate_gas_budget_with_policy,
+    GasBudgetEstimate, GasBudgetPolicy,
+};
+pub use coins::{fetch_sorted_coins, fetch_sorted_coins_with_mode, OwnedCoin};
+pub use common_derive::MoveStruct;
+pub use config::{Config, Network};
+pub use diagram::to_mermaid;
+pub use disasm::disassemble;
+pub use error::{DecodeMode, Error, Fetched, Result};
+#[cfg(any(test, feature = "fake"))]
+pub use fake::FakeNode;
+pub use fan_out::{build_fan_out_ptb, fan_out_coins, FanOutSource};
+pub use fixtures::{network_fixture_path, FixtureMode, FixtureServer};
+pub use gas::{
+    select_gas_coins, select_gas_payment, select_gas_payment_early, EarlySelection,
+    SelectionStrategy,
+};
+pub use gas_rewrite::{rewrite_gas_coin, rewrite_gas_coin_with_abi, GasCoinUse};
+pub use inspect::DevInspectOutcome;
+pub use labels::Labels;
+pub use lint::{check_moves, lint, lint_programmable, lint_with_abi, Lint};
+pub use merge::{build_merge_coins_ptb, MergePlan, MergeTransaction};
+pub use min_budget::{find_min_gas_budget, BudgetProbe, MinGasBudget};
+pub use move_fn::{MoveArgs, MoveFunction};
+pub use node::NodeApi;
+pub use pagination::{coins_stream, owned_object_pages_stream, owned_objects_stream};
+pub use pool::{GasLease, GasPool};
+pub use ptb::{MoveStruct, MoveType, Res, TypedPtb};
+pub use sweep::{
+    build_destroy_zero_ptbs, estimate_sweep_rebate, fetch_empty_gas_coins, SweepEstimate,
+};
+
+/// Dependencies of the code generated by `#[derive(MoveStruct)]` and `move_fn!`
+#[doc(hidden)]
+pub mod __private {
+    pub use anyhow;
+    pub use move_core_types;
+    pub use serde;
+}
+
+use futures::TryStreamExt;
+use sui_sdk::rpc_types::{
+    SuiObjectData, SuiObjectDataFilter, SuiObjectDataOptions, SuiObjectResponseQuery,
+};
+use sui_types::{
+    base_types::{ObjectRef, SuiAddress},
+    gas_coin::GasCoin,
 };
-use sui_types::{base_types::SuiAddress, gas_coin::GasCoin};
 
 pub async fn fetch_sorted_gas_coins(
-    rpc_client: &SuiClient,
+    rpc_client: &impl NodeApi,
+    sender: &SuiAddress,
+) -> Result<Vec<(SuiObjectData, u64)>> {
+    let fetched = fetch_sorted_gas_coins_with_mode(rpc_client, sender, DecodeMode::Strict).await?;
+    Ok(fetched.items)
+}
+
+/// Same as `fetch_sorted_gas_coins`, but in `DecodeMode::Skip` objects that fail to decode are
+/// collected in `Fetched::failures` instead of failing the whole call.
+pub async fn fetch_sorted_gas_coins_with_mode(
+    rpc_client: &impl NodeApi,
     sender: &SuiAddress,
-) -> anyhow::Result<Vec<(SuiObjectData, u64)>> {
-    let mut gas_objects: Vec<(SuiObjectData, u64)> = vec![];
-    let mut cursor = None;
-    loop {
-        let response = rpc_client
-            .read_api()
-            .get_owned_objects(
-                sender.clone(),
-                Some(SuiObjectResponseQuery {
-                    filter: Some(SuiObjectDataFilter::MatchAll(vec![
-                        SuiObjectDataFilter::StructType(GasCoin::type_()),
-                    ])),
-                    options: Some(SuiObjectDataOptions::full_content()),
-                }),
-                cursor,
-                None,
-            )
-            .await?;
-
-        let new_gas_objects: Vec<_> = response
-            .data
-            .into_iter()
-            .filter_map(|maybe_object| {
-                if let Some(object) = maybe_object.data {
-                    let gas_coin = GasCoin::try_from(&object).unwrap();
-                    let gas_balance = gas_coin.value();
-                    if gas_balance > 0 {
-                        Some((object, gas_balance))
-                    } else {
-                        None
-                    }
-                } else {
-                    None
-                }
-            })
-            .collect();
-
-        gas_objects.extend(new_gas_objects);
-
-        if !response.has_next_page {
-            break;
-        };
-        cursor = response.next_cursor;
+    mode: DecodeMode,
+) -> Result<Fetched<(SuiObjectData, u64)>> {
+    let mut fetched = Fetched::default();
+    let mut objects = Box::pin(owned_objects_stream(
+        rpc_client,
+        *sender,
+        gas_coins_query(),
+        None,
+    ));
+    while let Some(object) = objects.try_next().await? {
+        match decode_gas_coin(&object) {
+            Ok(gas_balance) if gas_balance > 0 => fetched.items.push((object, gas_balance)),
+            Ok(_) => {}
+            Err(e) if mode == DecodeMode::Skip => fetched.failures.push(e),
+            Err(e) => return Err(e),
+        }
     }
 
-    gas_objects.sort_by(|(_, a), (_, b)| b.cmp(a));
+    fetched.items.sort_by(|(_, a), (_, b)| b.cmp(a));
 
-    Ok(gas_objects)
+    Ok(fetched)
+}
+
+/// Lean alternative to `fetch_sorted_gas_coins` going through the coin read API, which only
+/// returns the object ref and balance of each coin instead of its full content.
+pub async fn fetch_sorted_gas_coin_refs(
+    rpc_client: &impl NodeApi,
+    sender: &SuiAddress,
+) -> Result<Vec<(ObjectRef, u64)>> {
+    let mut gas_coins: Vec<(ObjectRef, u64)> = vec![];
+    // `None` defaults to `0x2::sui::SUI`
+    let mut coins = Box::pin(coins_stream(rpc_client, *sender, None, None));
+    while let Some(coin) = coins.try_next().await? {
+        if coin.balance > 0 {
+            gas_coins.push((coin.object_ref(), coin.balance));
+        }
+    }
+
+    gas_coins.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    Ok(gas_coins)
+}
+
+/// Query for all SUI coins, with enough content to decode their balance
+pub fn gas_coins_query() -> SuiObjectResponseQuery {
+    SuiObjectResponseQuery {
+        filter: Some(SuiObjectDataFilter::MatchAll(vec![
+            SuiObjectDataFilter::StructType(GasCoin::type_()),
+        ])),
+        options: Some(SuiObjectDataOptions::full_content()),
+    }
+}
+
+pub(crate) fn decode_gas_coin(object: &SuiObjectData) -> Result<u64> {
+    if object.content.is_none() {
+        return Err(Error::MissingContent(object.object_id));
+    }
+    let gas_coin = GasCoin::try_from(object).map_err(|e| Error::decode(object.object_id, e))?;
+    Ok(gas_coin.value())
 }
@@ -0,0 +1,19 @@
+//! Protocol limits relevant to building transactions, mirroring the values of the protocol config.
+
+/// Max number of gas objects a transaction can pay with (`max_gas_payment_objects`)
+pub const MAX_GAS_PAYMENT_OBJECTS: usize = 256;
+
+/// Max number of input objects of a transaction (`max_input_objects`)
+pub const MAX_INPUT_OBJECTS: usize = 2048;
+
+/// Max number of commands in a programmable transaction (`max_programmable_tx_commands`)
+pub const MAX_COMMANDS: usize = 1024;
+
+/// Max number of arguments of a single command (`max_arguments`)
+pub const MAX_ARGUMENTS: usize = 512;
+
+/// Max gas budget of a transaction (`max_tx_gas`), 50 Sui
+pub const MAX_GAS_BUDGET: u64 = 50_000_000_000;
+
+/// Max serialized size of a transaction (`max_tx_size_bytes`)
+pub const MAX_TX_SIZE_BYTES: usize = 128 * 1024;
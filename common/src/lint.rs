@@ -0,0 +1,465 @@
+//! Static checks of a transaction, catching mistakes the node would only report on execution.
+
+use std::{collections::HashMap, fmt};
+
+use sui_types::{
+    base_types::{ObjectID, ObjectRef},
+    transaction::{
+        Argument, CallArg, Command, ObjectArg, ProgrammableTransaction, TransactionData,
+        TransactionDataAPI, TransactionKind,
+    },
+};
+
+use crate::abi::{Abi, Abilities, Usage};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Lint {
+    /// An owned object input that is also a gas payment coin: the protocol rejects the
+    /// transaction for using the same object twice
+    GasCoinAsInput { input: u16, object_id: ObjectID },
+    /// A value used after an earlier command took it by value
+    UseAfterMove {
+        command: u16,
+        argument: Argument,
+        moved_by: u16,
+    },
+    /// The gas coin taken by value by anything but `TransferObjects`
+    GasCoinByValue { command: u16 },
+    /// A result without `drop` that no command consumes, aborting the transaction at the end
+    UnusedWithoutDrop { command: u16, result: u16 },
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Lint::GasCoinAsInput { input, object_id } => write!(
+                f,
+                "Input {} is object {}, which is also used for gas payment. Pass \
+                 `Argument::GasCoin` instead, with a `SplitCoins(Argument::GasCoin, [amount])` \
+                 for the balance the PTB needs, and keep the rest for gas.",
+                input, object_id
+            ),
+            Lint::UseAfterMove {
+                command,
+                argument,
+                moved_by,
+            } => write!(
+                f,
+                "Command {} uses {:?}, which command {} already took by value",
+                command, argument, moved_by
+            ),
+            Lint::GasCoinByValue { command } => write!(
+                f,
+                "Command {} takes the gas coin by value, only `TransferObjects` can. Split the \
+                 balance needed with `SplitCoins(Argument::GasCoin, [amount])` instead.",
+                command
+            ),
+            Lint::UnusedWithoutDrop { command, result } => write!(
+                f,
+                "Result {} of command {} cannot be dropped and is never used by value, transfer \
+                 or destroy it",
+                result, command
+            ),
+        }
+    }
+}
+
+/// Check a transaction before submitting it, knowing the framework functions with a descriptor.
+/// Only programmable transactions are checked.
+pub fn lint(tx_data: &TransactionData) -> Vec<Lint> {
+    lint_with_abi(tx_data, &Abi::framework())
+}
+
+/// Same as `lint`, with the signatures of other functions, e.g. from `Abi::add_modules`
+pub fn lint_with_abi(tx_data: &TransactionData, abi: &Abi) -> Vec<Lint> {
+    match tx_data.kind() {
+        TransactionKind::ProgrammableTransaction(pt) => {
+            let mut lints = lint_programmable(pt, tx_data.gas());
+            lints.extend(check_moves(pt, abi));
+            lints
+        }
+        _ => vec![],
+    }
+}
+
+/// Gas payment check of `lint`, for a PTB that is still to be paired with `gas_payment`
+pub fn lint_programmable(pt: &ProgrammableTransaction, gas_payment: &[ObjectRef]) -> Vec<Lint> {
+    pt.inputs
+        .iter()
+        .enumerate()
+        .filter_map(|(input, call_arg)| match call_arg {
+            CallArg::Object(ObjectArg::ImmOrOwnedObject((object_id, _, _)))
+                if gas_payment.iter().any(|(id, _, _)| id == object_id) =>
+            {
+                Some(Lint::GasCoinAsInput {
+                    input: input as u16,
+                    object_id: *object_id,
+                })
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// A value a PTB argument can refer to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Value {
+    GasCoin,
+    Input(u16),
+    Result(u16, u16),
+}
+
+impl From<Argument> for Value {
+    fn from(argument: Argument) -> Self {
+        match argument {
+            Argument::GasCoin => Value::GasCoin,
+            Argument::Input(input) => Value::Input(input),
+            Argument::Result(command) => Value::Result(command, 0),
+            Argument::NestedResult(command, result) => Value::Result(command, result),
+        }
+    }
+}
+
+/// Follow values through the commands as the Move runtime does: arguments taken by value are
+/// moved unless they have `copy`, and results left at the end must have `drop`. Calls to
+/// functions missing from `abi`, and values of unknown types, are assumed to be fine.
+pub fn check_moves(pt: &ProgrammableTransaction, abi: &Abi) -> Vec<Lint> {
+    let mut lints = vec![];
+    // Abilities of each result, `None` when unknown
+    let mut results: HashMap<Value, Option<Abilities>> = HashMap::new();
+    let mut moved: HashMap<Value, u16> = HashMap::new();
+
+    for (index, command) in pt.commands.iter().enumerate() {
+        let index = index as u16;
+
+        let arguments: Vec<(Argument, Option<Usage>)> = match command {
+            Command::MoveCall(call) => {
+                match abi.function(call.package, call.module.as_str(), call.function.as_str()) {
+                    Some(signature) if signature.parameters.len() == call.arguments.len() => call
+                        .arguments
+                        .iter()
+                        .zip(&signature.parameters)
+                        .map(|(argument, (usage, _))| (*argument, Some(*usage)))
+                        .collect(),
+                    _ => call
+                        .arguments
+                        .iter()
+                        .map(|argument| (*argument, None))
+                        .collect(),
+                }
+            }
+            Command::TransferObjects(objects, recipient) => objects
+                .iter()
+                .chain([recipient])
+                .map(|argument| (*argument, Some(Usage::ByValue)))
+                .collect(),
+            Command::SplitCoins(coin, amounts) => [(*coin, Some(Usage::ByMutRef))]
+                .into_iter()
+                .chain(amounts.iter().map(|amount| (*amount, Some(Usage::ByValue))))
+                .collect(),
+            Command::MergeCoins(destination, sources) => [(*destination, Some(Usage::ByMutRef))]
+                .into_iter()
+                .chain(sources.iter().map(|source| (*source, Some(Usage::ByValue))))
+                .collect(),
+            Command::MakeMoveVec(_, elements) => elements
+                .iter()
+                .map(|element| (*element, Some(Usage::ByValue)))
+                .collect(),
+            Command::Publish(..) => vec![],
+            Command::Upgrade(_, _, _, ticket) => vec![(*ticket, Some(Usage::ByValue))],
+        };
+
+        for (argument, usage) in arguments {
+            let value = Value::from(argument);
+            if let Some(moved_by) = moved.get(&value) {
+                lints.push(Lint::UseAfterMove {
+                    command: index,
+                    argument,
+                    moved_by: *moved_by,
+                });
+                continue;
+            }
+            if usage != Some(Usage::ByValue) {
+                continue;
+            }
+
+            let copy = match value {
+                Value::GasCoin => {
+                    if !matches!(command, Command::TransferObjects(..)) {
+                        lints.push(Lint::GasCoinByValue { command: index });
+                    }
+                    false
+                }
+                Value::Input(input) => match pt.inputs.get(input as usize) {
+                    Some(CallArg::Pure(_)) => true,
+                    // Shared objects cannot be moved anyway, which the runtime reports on its own
+                    Some(CallArg::Object(_)) => false,
+                    None => continue,
+                },
+                Value::Result(..) => match results.get(&value) {
+                    Some(Some(abilities)) => abilities.copy,
+                    _ => continue,
+                },
+            };
+            if !copy {
+                moved.insert(value, index);
+            }
+        }
+
+        let produced: Vec<Option<Abilities>> = match command {
+            Command::MoveCall(call) => {
+                match abi.function(call.package, call.module.as_str(), call.function.as_str()) {
+                    Some(signature) => signature
+                        .returns
+                        .iter()
+                        .map(|type_| abi.instantiated_abilities(type_, &call.type_arguments))
+                        .collect(),
+                    None => vec![],
+                }
+            }
+            Command::SplitCoins(_, amounts) => vec![Some(Abilities::NONE); amounts.len()],
+            Command::MakeMoveVec(type_tag, elements) => {
+                let abilities = match (type_tag, elements.first()) {
+                    (Some(type_tag), _) => abi.abilities(type_tag),
+                    (None, Some(element)) => match Value::from(*element) {
+                        Value::Input(input) => match pt.inputs.get(input as usize) {
+                            Some(CallArg::Pure(_)) => Some(Abilities::COPY_DROP),
+                            _ => None,
+                        },
+                        value @ Value::Result(..) => results.get(&value).copied().flatten(),
+                        Value::GasCoin => Some(Abilities::NONE),
+                    },
+                    (None, None) => None,
+                };
+                vec![abilities]
+            }
+            // `UpgradeCap` and `UpgradeReceipt` respectively
+            Command::Publish(..) | Command::Upgrade(..) => vec![Some(Abilities::NONE)],
+            Command::TransferObjects(..) | Command::MergeCoins(..) => vec![],
+        };
+        for (result, abilities) in produced.into_iter().enumerate() {
+            results.insert(Value::Result(index, result as u16), abilities);
+        }
+    }
+
+    let mut unused: Vec<(u16, u16)> = results
+        .iter()
+        .filter(|(value, abilities)| {
+            matches!(abilities, Some(abilities) if !abilities.drop) && !moved.contains_key(value)
+        })
+        .filter_map(|(value, _)| match value {
+            Value::Result(command, result) => Some((*command, *result)),
+            _ => None,
+        })
+        .collect();
+    unused.sort();
+    lints.extend(
+        unused
+            .into_iter()
+            .map(|(command, result)| Lint::UnusedWithoutDrop { command, result }),
+    );
+
+    lints
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_types::{
+        base_types::{ObjectDigest, SequenceNumber, SuiAddress},
+        gas_coin::GAS,
+        programmable_transaction_builder::ProgrammableTransactionBuilder,
+        transaction::ProgrammableMoveCall,
+        Identifier, TypeTag, SUI_FRAMEWORK_PACKAGE_ID,
+    };
+
+    use super::*;
+    use crate::{fake::object_id, rewrite_gas_coin, GasCoinUse};
+
+    fn coin_ref(index: u64) -> ObjectRef {
+        (
+            object_id(index),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::new([0; 32]),
+        )
+    }
+
+    fn owned_input(index: u64) -> CallArg {
+        CallArg::Object(ObjectArg::ImmOrOwnedObject(coin_ref(index)))
+    }
+
+    fn recipient_input() -> CallArg {
+        CallArg::Pure(bcs::to_bytes(&SuiAddress::ZERO).unwrap())
+    }
+
+    /// Call to a framework function known to `Abi::framework`
+    fn framework_call(
+        module: &str,
+        function: &str,
+        type_arguments: Vec<TypeTag>,
+        arguments: Vec<Argument>,
+    ) -> Command {
+        Command::MoveCall(Box::new(ProgrammableMoveCall {
+            package: SUI_FRAMEWORK_PACKAGE_ID,
+            module: Identifier::new(module).unwrap(),
+            function: Identifier::new(function).unwrap(),
+            type_arguments,
+            arguments,
+        }))
+    }
+
+    fn tx_data(pt: ProgrammableTransaction, gas_payment: Vec<ObjectRef>) -> TransactionData {
+        TransactionData::new_programmable(SuiAddress::ZERO, gas_payment, pt, 10_000_000, 1_000)
+    }
+
+    /// The naive PTB of the single gas coin example, reading the value of `coin` and transferring
+    /// it back to the sender
+    fn single_gas_coin_pt(coin: ObjectRef) -> ProgrammableTransaction {
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        let coin_arg = pt_builder.obj(ObjectArg::ImmOrOwnedObject(coin)).unwrap();
+        let value = pt_builder.programmable_move_call(
+            SUI_FRAMEWORK_PACKAGE_ID,
+            Identifier::new("coin").unwrap(),
+            Identifier::new("value").unwrap(),
+            vec![GAS::type_tag()],
+            vec![coin_arg],
+        );
+        let denominator = pt_builder.pure(9u64).unwrap();
+        pt_builder.programmable_move_call(
+            SUI_FRAMEWORK_PACKAGE_ID,
+            Identifier::new("math").unwrap(),
+            Identifier::new("divide_and_round_up").unwrap(),
+            vec![],
+            vec![value, denominator],
+        );
+        pt_builder.transfer_args(SuiAddress::ZERO, vec![coin_arg]);
+        pt_builder.finish()
+    }
+
+    #[test]
+    fn gas_payment_coin_as_input_is_flagged() {
+        let primary_coin = coin_ref(1);
+
+        let lints = lint(&tx_data(
+            single_gas_coin_pt(primary_coin),
+            vec![primary_coin],
+        ));
+
+        assert_eq!(
+            lints,
+            vec![Lint::GasCoinAsInput {
+                input: 0,
+                object_id: primary_coin.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn rewritten_gas_coin_uses_pass() {
+        let primary_coin = coin_ref(1);
+        let pt = rewrite_gas_coin(
+            &single_gas_coin_pt(primary_coin),
+            &[primary_coin],
+            GasCoinUse::Split { amount: 1_000 },
+        )
+        .unwrap();
+
+        assert!(lint(&tx_data(pt, vec![primary_coin])).is_empty());
+    }
+
+    #[test]
+    fn owned_inputs_outside_the_gas_payment_pass() {
+        let pt = single_gas_coin_pt(coin_ref(1));
+
+        assert!(lint(&tx_data(pt, vec![coin_ref(2)])).is_empty());
+    }
+
+    #[test]
+    fn merged_coin_reused_is_a_use_after_move() {
+        let pt = ProgrammableTransaction {
+            inputs: vec![owned_input(1), owned_input(2), recipient_input()],
+            commands: vec![
+                Command::MergeCoins(Argument::Input(0), vec![Argument::Input(1)]),
+                Command::TransferObjects(vec![Argument::Input(1)], Argument::Input(2)),
+            ],
+        };
+
+        assert_eq!(
+            check_moves(&pt, &Abi::framework()),
+            vec![Lint::UseAfterMove {
+                command: 1,
+                argument: Argument::Input(1),
+                moved_by: 0,
+            }]
+        );
+    }
+
+    #[test]
+    fn gas_coin_merged_away_is_taken_by_value() {
+        let pt = ProgrammableTransaction {
+            inputs: vec![owned_input(1)],
+            commands: vec![Command::MergeCoins(
+                Argument::Input(0),
+                vec![Argument::GasCoin],
+            )],
+        };
+
+        assert_eq!(
+            check_moves(&pt, &Abi::framework()),
+            vec![Lint::GasCoinByValue { command: 0 }]
+        );
+    }
+
+    #[test]
+    fn unconsumed_kiosk_owner_cap_cannot_be_dropped() {
+        let pt = ProgrammableTransaction {
+            inputs: vec![recipient_input()],
+            commands: vec![
+                framework_call("kiosk", "new", vec![], vec![]),
+                Command::TransferObjects(vec![Argument::NestedResult(0, 0)], Argument::Input(0)),
+            ],
+        };
+
+        assert_eq!(
+            check_moves(&pt, &Abi::framework()),
+            vec![Lint::UnusedWithoutDrop {
+                command: 0,
+                result: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn copy_and_drop_values_can_be_reused_or_left() {
+        let pt = ProgrammableTransaction {
+            inputs: vec![owned_input(1), CallArg::Pure(bcs::to_bytes(&9u64).unwrap())],
+            commands: vec![
+                framework_call(
+                    "coin",
+                    "value",
+                    vec![GAS::type_tag()],
+                    vec![Argument::Input(0)],
+                ),
+                framework_call(
+                    "math",
+                    "diff",
+                    vec![],
+                    vec![Argument::Result(0), Argument::Result(0)],
+                ),
+                framework_call(
+                    "math",
+                    "divide_and_round_up",
+                    vec![],
+                    vec![Argument::Input(1), Argument::Input(1)],
+                ),
+                framework_call(
+                    "math",
+                    "diff",
+                    vec![],
+                    vec![Argument::Result(0), Argument::Input(1)],
+                ),
+            ],
+        };
+
+        assert!(check_moves(&pt, &Abi::framework()).is_empty());
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::VecDeque;
+
+use anyhow::{bail, ensure};
+use sui_sdk::rpc_types::SuiObjectData;
+use sui_types::{
+    base_types::ObjectRef,
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{Argument, CallArg, Command, ObjectArg, ProgrammableTransaction},
+};
+
+use crate::limits::{MAX_ARGUMENTS, MAX_COMMANDS, MAX_GAS_PAYMENT_OBJECTS, MAX_INPUT_OBJECTS};
+
+/// Max number of coins merged through `MergeCoins` in a single transaction. Kept well below
+/// `MAX_INPUT_OBJECTS` as ~1.8k object refs alone would exceed `MAX_TX_SIZE_BYTES`.
+pub const MAX_MERGED_INPUTS_PER_TX: usize = 1024;
+
+/// Max number of source coins of a single `MergeCoins` command, the destination being an argument too
+const MAX_MERGE_SOURCES: usize = MAX_ARGUMENTS - 1;
+
+/// A consolidation transaction: all gas payment coins get smashed into the first one by the
+/// protocol, and the PTB merges every other coin into the resulting gas coin.
+#[derive(Debug, Clone)]
+pub struct MergeTransaction {
+    pub gas_payment: Vec<ObjectRef>,
+    pub pt: ProgrammableTransaction,
+}
+
+impl MergeTransaction {
+    /// Number of coins this transaction removes from the owner's coin count: every gas payment coin
+    /// but the first, and every coin merged by the PTB
+    pub fn merged_count(&self) -> usize {
+        let merged_sources: usize = self
+            .pt
+            .commands
+            .iter()
+            .map(|command| match command {
+                Command::MergeCoins(_, sources) => sources.len(),
+                _ => 0,
+            })
+            .sum();
+        self.gas_payment.len().saturating_sub(1) + merged_sources
+    }
+}
+
+/// Transactions of `build_merge_coins_ptb`, to be executed before planning any further round
+#[derive(Debug, Clone)]
+pub struct MergePlan {
+    pub transactions: Vec<MergeTransaction>,
+    /// Number of coins left once every transaction is executed. Above the target when the coins
+    /// do not fit in one round: the coins left by the transactions are only known once executed,
+    /// so plan again from a fresh `fetch_sorted_gas_coins` then.
+    pub coin_count: usize,
+}
+
+/// Merge `sources` into `destination` with as few `MergeCoins` commands as the argument limit allows.
+pub fn merge_into(
+    pt_builder: &mut ProgrammableTransactionBuilder,
+    destination: Argument,
+    sources: &[ObjectRef],
+) -> anyhow::Result<()> {
+    ensure!(
+        sources.len() <= MAX_INPUT_OBJECTS,
+        "Cannot merge {} coins in a single PTB, at most {} input objects are allowed",
+        sources.len(),
+        MAX_INPUT_OBJECTS
+    );
+    ensure!(
+        sources.len().div_ceil(MAX_MERGE_SOURCES) <= MAX_COMMANDS,
+        "Merging {} coins would exceed the command limit",
+        sources.len()
+    );
+
+    for chunk in sources.chunks(MAX_MERGE_SOURCES) {
+        let source_args = chunk
+            .iter()
+            .map(|coin| pt_builder.obj(ObjectArg::ImmOrOwnedObject(*coin)))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        pt_builder.command(Command::MergeCoins(destination, source_args));
+    }
+
+    Ok(())
+}
+
+/// Plan the transactions bringing `coins` (as returned by `fetch_sorted_gas_coins`) down to
+/// `target_count` coins.
+///
+/// Each transaction uses gas smashing: its richest coins pay for gas (covering `gas_budget`), dust
+/// coins fill the rest of the gas payment for free, and anything beyond is merged into
+/// `Argument::GasCoin`. Transactions spend disjoint sets of coins so they can be submitted in
+/// parallel, and each one leaves a single coin behind. `MergePlan::coin_count` tells whether
+/// another round is needed.
+///
+/// Fails if the richest coins cannot cover `gas_budget`. Once some transactions are planned, coins
+/// unable to pay for another one are left to the next round instead.
+pub fn build_merge_coins_ptb(
+    coins: &[(SuiObjectData, u64)],
+    target_count: usize,
+    gas_budget: u64,
+) -> anyhow::Result<MergePlan> {
+    ensure!(target_count > 0, "Cannot consolidate down to 0 coins");
+
+    let mut sorted: Vec<&(SuiObjectData, u64)> = coins.iter().collect();
+    sorted.sort_by(|(_, a), (_, b)| b.cmp(a));
+    let mut remaining: VecDeque<&(SuiObjectData, u64)> = sorted.into();
+
+    let mut coin_count = remaining.len();
+    let mut transactions = vec![];
+
+    while coin_count > target_count {
+        // A transaction turning `n` coins into 1 removes `n - 1` coins
+        let max_coins = (coin_count - target_count + 1)
+            .min(remaining.len())
+            .min(MAX_GAS_PAYMENT_OBJECTS + MAX_MERGED_INPUTS_PER_TX);
+        if max_coins < 2 {
+            break;
+        }
+
+        // richest coins first until the budget is covered
+        let mut gas_coins = vec![];
+        let mut gas_total = 0u128;
+        while gas_total < gas_budget as u128
+            && gas_coins.len() < MAX_GAS_PAYMENT_OBJECTS
+            && gas_coins.len() < max_coins
+        {
+            let Some((coin, balance)) = remaining.pop_front() else {
+                break;
+            };
+            gas_total += *balance as u128;
+            gas_coins.push(coin.object_ref());
+        }
+        if gas_total < gas_budget as u128 {
+            // What is left is dust, which the planned transactions merge into coins able to pay
+            // for the next round
+            if !transactions.is_empty() {
+                break;
+            }
+            bail!(
+                "Cannot cover a gas budget of {} with the {} richest coins",
+                gas_budget,
+                gas_coins.len()
+            );
+        }
+
+        // then dust, smashed along with the gas payment first, merged through the PTB after that
+        let mut sources = vec![];
+        while gas_coins.len() + sources.len() < max_coins {
+            let Some((coin, _)) = remaining.pop_back() else {
+                break;
+            };
+            if gas_coins.len() < MAX_GAS_PAYMENT_OBJECTS {
+                gas_coins.push(coin.object_ref());
+            } else {
+                sources.push(coin.object_ref());
+            }
+        }
+
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        merge_into(&mut pt_builder, Argument::GasCoin, &sources)?;
+
+        let transaction = MergeTransaction {
+            gas_payment: gas_coins,
+            pt: pt_builder.finish(),
+        };
+        coin_count -= transaction.merged_count();
+        transactions.push(transaction);
+    }
+
+    Ok(MergePlan {
+        transactions,
+        coin_count,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use sui_types::base_types::ObjectID;
+
+    use super::*;
+    use crate::fake::{object_id, sui_coin_object};
+
+    /// Coins with the given balances, in order
+    fn coins_with_balances(balances: impl IntoIterator<Item = u64>) -> Vec<(SuiObjectData, u64)> {
+        (1..)
+            .zip(balances)
+            .map(|(index, balance)| (sui_coin_object(object_id(index), balance), balance))
+            .collect()
+    }
+
+    /// `count` coins of 1_000 MIST, but for a richer first one
+    fn coins(count: usize) -> Vec<(SuiObjectData, u64)> {
+        coins_with_balances((0..count).map(|index| if index == 0 { 1_000_000 } else { 1_000 }))
+    }
+
+    /// `rich` coins of 1 SUI, then dust of 1_000 MIST up to `count` coins
+    fn dusty_coins(rich: usize, count: usize) -> Vec<(SuiObjectData, u64)> {
+        coins_with_balances((0..count).map(
+            |index| {
+                if index < rich {
+                    1_000_000_000
+                } else {
+                    1_000
+                }
+            },
+        ))
+    }
+
+    /// Gas budget of a merge transaction on mainnet, 0.05 SUI
+    const REALISTIC_GAS_BUDGET: u64 = 50_000_000;
+
+    fn spent_coins(plan: &MergePlan) -> Vec<ObjectID> {
+        plan.transactions
+            .iter()
+            .flat_map(|transaction| {
+                let inputs = transaction.pt.inputs.iter().map(|input| match input {
+                    CallArg::Object(ObjectArg::ImmOrOwnedObject((id, _, _))) => *id,
+                    input => panic!("Unexpected input {:?}", input),
+                });
+                transaction
+                    .gas_payment
+                    .iter()
+                    .map(|(id, _, _)| *id)
+                    .chain(inputs)
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn small_sets_are_smashed_through_the_gas_payment() {
+        let plan = build_merge_coins_ptb(&coins(10), 1, 500_000).unwrap();
+
+        assert_eq!(plan.transactions.len(), 1);
+        let transaction = &plan.transactions[0];
+        assert_eq!(transaction.gas_payment.len(), 10);
+        assert_eq!(transaction.gas_payment[0], coins(10)[0].0.object_ref());
+        assert!(transaction.pt.commands.is_empty());
+        assert_eq!(transaction.merged_count(), 9);
+        assert_eq!(plan.coin_count, 1);
+    }
+
+    #[test]
+    fn coins_beyond_the_gas_payment_are_merged_into_the_gas_coin() {
+        let plan = build_merge_coins_ptb(&coins(300), 1, 500_000).unwrap();
+
+        assert_eq!(plan.transactions.len(), 1);
+        let transaction = &plan.transactions[0];
+        assert_eq!(transaction.gas_payment.len(), MAX_GAS_PAYMENT_OBJECTS);
+        assert_eq!(transaction.pt.inputs.len(), 300 - MAX_GAS_PAYMENT_OBJECTS);
+        assert!(transaction
+            .pt
+            .commands
+            .iter()
+            .all(|command| matches!(command, Command::MergeCoins(Argument::GasCoin, _))));
+        assert_eq!(plan.coin_count, 1);
+    }
+
+    #[test]
+    fn stops_at_the_target_count() {
+        let plan = build_merge_coins_ptb(&coins(10), 4, 500_000).unwrap();
+
+        assert_eq!(plan.transactions.len(), 1);
+        assert_eq!(plan.transactions[0].merged_count(), 6);
+        assert_eq!(plan.coin_count, 4);
+    }
+
+    #[test]
+    fn nothing_to_do_at_the_target_count() {
+        let plan = build_merge_coins_ptb(&coins(4), 4, 500_000).unwrap();
+
+        assert!(plan.transactions.is_empty());
+        assert_eq!(plan.coin_count, 4);
+    }
+
+    #[test]
+    fn large_sets_are_spread_over_disjoint_transactions() {
+        let coins = coins(2000);
+        let plan = build_merge_coins_ptb(&coins, 1, 3_000).unwrap();
+
+        let spent = spent_coins(&plan);
+        assert_eq!(spent.len(), 2000);
+        assert_eq!(spent.iter().collect::<HashSet<_>>().len(), 2000);
+        for transaction in &plan.transactions {
+            assert!(transaction.gas_payment.len() <= MAX_GAS_PAYMENT_OBJECTS);
+            assert!(transaction.pt.inputs.len() <= MAX_MERGED_INPUTS_PER_TX);
+        }
+    }
+
+    #[test]
+    fn reports_coins_left_for_another_round() {
+        let plan = build_merge_coins_ptb(&coins(2000), 1, 3_000).unwrap();
+
+        // Each transaction leaves a coin, which only a later round can merge
+        assert_eq!(plan.coin_count, plan.transactions.len());
+        assert_eq!(plan.coin_count, 2);
+    }
+
+    #[test]
+    fn realistic_budgets_are_paid_by_the_rich_coins() {
+        let coins = dusty_coins(2, 2000);
+        let plan = build_merge_coins_ptb(&coins, 1, REALISTIC_GAS_BUDGET).unwrap();
+
+        assert_eq!(plan.transactions.len(), 2);
+        for (transaction, (rich_coin, _)) in plan.transactions.iter().zip(&coins) {
+            assert_eq!(transaction.gas_payment[0], rich_coin.object_ref());
+        }
+        let spent = spent_coins(&plan);
+        assert_eq!(spent.len(), 2000);
+        assert_eq!(spent.iter().collect::<HashSet<_>>().len(), 2000);
+        assert_eq!(plan.coin_count, 2);
+    }
+
+    #[test]
+    fn rounds_short_of_gas_are_left_for_later() {
+        let coins = dusty_coins(1, 2000);
+        let plan = build_merge_coins_ptb(&coins, 1, REALISTIC_GAS_BUDGET).unwrap();
+
+        // The dust left cannot pay for a second transaction until the first one merged it
+        assert_eq!(plan.transactions.len(), 1);
+        let transaction = &plan.transactions[0];
+        assert_eq!(transaction.gas_payment[0], coins[0].0.object_ref());
+        assert_eq!(
+            transaction.merged_count(),
+            MAX_GAS_PAYMENT_OBJECTS + MAX_MERGED_INPUTS_PER_TX - 1
+        );
+        assert_eq!(plan.coin_count, 2000 - transaction.merged_count());
+    }
+
+    #[test]
+    fn merged_count_follows_the_merge_commands() {
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        let coin_refs: Vec<_> = coins(3).iter().map(|(coin, _)| coin.object_ref()).collect();
+        merge_into(&mut pt_builder, Argument::GasCoin, &coin_refs).unwrap();
+        let transaction = MergeTransaction {
+            gas_payment: vec![coins(5)[4].0.object_ref()],
+            pt: pt_builder.finish(),
+        };
+        assert_eq!(transaction.merged_count(), 3);
+
+        let transaction = MergeTransaction {
+            gas_payment: vec![],
+            pt: ProgrammableTransactionBuilder::new().finish(),
+        };
+        assert_eq!(transaction.merged_count(), 0);
+    }
+
+    #[test]
+    fn uncovered_budgets_are_errors() {
+        assert!(build_merge_coins_ptb(&coins(10), 1, 2_000_000).is_err());
+        assert!(build_merge_coins_ptb(&coins(10), 0, 500_000).is_err());
+    }
+}
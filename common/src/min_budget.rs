@@ -0,0 +1,281 @@
+//! Search for the smallest gas budget a transaction still succeeds with, for bots where every
+//! reserved MIST counts.
+
+use std::fmt;
+
+use anyhow::{bail, ensure};
+use sui_sdk::rpc_types::{SuiExecutionStatus, SuiTransactionBlockEffectsAPI};
+use sui_types::{
+    base_types::{ObjectRef, SuiAddress},
+    execution_status::ExecutionFailureStatus,
+    gas::GasCostSummary,
+    transaction::{ProgrammableTransaction, TransactionData, TransactionDataAPI},
+};
+
+use crate::NodeApi;
+
+/// Prefix of the `UserInputError::GasBudgetTooLow { gas_budget, min_budget }` rendering in the
+/// RPC error returned by the node when rejecting a budget below the minimum transaction cost,
+/// before any execution
+const GAS_BUDGET_TOO_LOW: &str = "GasBudgetTooLow {";
+
+/// One dry run of the search
+#[derive(Debug, Clone)]
+pub struct BudgetProbe {
+    pub gas_budget: u64,
+    /// Costs of the successful dry runs, `None` for those running out of gas
+    pub gas_cost_summary: Option<GasCostSummary>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MinGasBudget {
+    pub gas_budget: u64,
+    /// Costs of the dry run at `gas_budget`
+    pub gas_cost_summary: GasCostSummary,
+    /// Every dry run, in order
+    pub trace: Vec<BudgetProbe>,
+}
+
+impl fmt::Display for MinGasBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for probe in &self.trace {
+            match &probe.gas_cost_summary {
+                Some(_) => writeln!(f, "{:>14} ok", probe.gas_budget)?,
+                None => writeln!(f, "{:>14} insufficient gas", probe.gas_budget)?,
+            }
+        }
+        write!(
+            f,
+            "Min gas budget {}: computation {}, storage {}, rebate {}, net {}",
+            self.gas_budget,
+            self.gas_cost_summary.computation_cost,
+            self.gas_cost_summary.storage_cost,
+            self.gas_cost_summary.storage_rebate,
+            self.gas_cost_summary.net_gas_usage()
+        )
+    }
+}
+
+/// Binary search the smallest budget in `0..=max_budget` under which a dry run of `pt` succeeds,
+/// to within `precision` (e.g. the gas price, as computation costs are charged in units of it).
+/// Failures other than running out of gas abort the search, as no budget would fix them.
+///
+/// Against a `FakeNode`, `with_min_gas_budget` models a transaction running out of gas below a
+/// given budget.
+pub async fn find_min_gas_budget(
+    rpc_client: &impl NodeApi,
+    sender: SuiAddress,
+    pt: ProgrammableTransaction,
+    gas_payment: Vec<ObjectRef>,
+    max_budget: u64,
+    precision: u64,
+) -> anyhow::Result<MinGasBudget> {
+    ensure!(precision > 0, "Search precision must be positive");
+    let gas_price = rpc_client.get_reference_gas_price().await?;
+
+    let tx_data = |gas_budget: u64| {
+        TransactionData::new_programmable(
+            sender,
+            gas_payment.clone(),
+            pt.clone(),
+            gas_budget,
+            gas_price,
+        )
+    };
+    let mut trace = vec![];
+
+    let Some(mut gas_cost_summary) = probe(rpc_client, tx_data(max_budget), &mut trace).await?
+    else {
+        bail!(
+            "Dry run runs out of gas even with a budget of {}",
+            max_budget
+        );
+    };
+
+    // Budgets below `low` are known to run out of gas, `high` is known to be enough
+    let mut low = 0;
+    let mut high = max_budget;
+    while high - low >= precision {
+        let gas_budget = low + (high - low) / 2;
+        match probe(rpc_client, tx_data(gas_budget), &mut trace).await? {
+            Some(summary) => {
+                high = gas_budget;
+                gas_cost_summary = summary;
+            }
+            None => low = gas_budget + 1,
+        }
+    }
+
+    Ok(MinGasBudget {
+        gas_budget: high,
+        gas_cost_summary,
+        trace,
+    })
+}
+
+/// Dry run `tx_data`, adding it to `trace`. Returns the costs if it succeeds, `None` if it runs
+/// out of gas.
+async fn probe(
+    rpc_client: &impl NodeApi,
+    tx_data: TransactionData,
+    trace: &mut Vec<BudgetProbe>,
+) -> anyhow::Result<Option<GasCostSummary>> {
+    let gas_budget = tx_data.gas_budget();
+    let gas_cost_summary = dry_run(rpc_client, tx_data).await?;
+    trace.push(BudgetProbe {
+        gas_budget,
+        gas_cost_summary: gas_cost_summary.clone(),
+    });
+    Ok(gas_cost_summary)
+}
+
+async fn dry_run(
+    rpc_client: &impl NodeApi,
+    tx_data: TransactionData,
+) -> anyhow::Result<Option<GasCostSummary>> {
+    let gas_budget = tx_data.gas_budget();
+    let response = match rpc_client.dry_run_transaction_block(tx_data).await {
+        Ok(response) => response,
+        Err(e) if e.to_string().contains(GAS_BUDGET_TOO_LOW) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    match response.effects.status() {
+        SuiExecutionStatus::Success => Ok(Some(response.effects.gas_cost_summary().clone())),
+        SuiExecutionStatus::Failure { error } if is_insufficient_gas(error) => Ok(None),
+        SuiExecutionStatus::Failure { error } => {
+            bail!("Dry run with a budget of {} failed: {}", gas_budget, error)
+        }
+    }
+}
+
+/// Whether a failed status is the execution running out of gas. The node renders the
+/// `ExecutionFailureStatus` of failed transactions with `Debug`, followed by ` in command {}` for
+/// failures attributed to a command, which running out of gas never is.
+fn is_insufficient_gas(error: &str) -> bool {
+    error == format!("{:?}", ExecutionFailureStatus::InsufficientGas)
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_types::programmable_transaction_builder::ProgrammableTransactionBuilder;
+
+    use super::*;
+    use crate::{
+        fake::{dry_run_response, insufficient_gas_status},
+        Error, FakeNode,
+    };
+
+    fn cost(computation_cost: u64) -> GasCostSummary {
+        GasCostSummary {
+            computation_cost,
+            storage_cost: 0,
+            storage_rebate: 0,
+            non_refundable_storage_fee: 0,
+        }
+    }
+
+    async fn search(
+        node: &FakeNode,
+        max_budget: u64,
+        precision: u64,
+    ) -> anyhow::Result<MinGasBudget> {
+        let pt = ProgrammableTransactionBuilder::new().finish();
+        find_min_gas_budget(node, SuiAddress::ZERO, pt, vec![], max_budget, precision).await
+    }
+
+    fn probed_budgets(node: &FakeNode) -> Vec<u64> {
+        node.dry_runs().iter().map(|tx| tx.gas_budget()).collect()
+    }
+
+    #[tokio::test]
+    async fn finds_the_exact_threshold_at_precision_one() {
+        let node = FakeNode::new().with_min_gas_budget(3_001, cost(2_500));
+
+        let min_budget = search(&node, 10_000, 1).await.unwrap();
+
+        assert_eq!(min_budget.gas_budget, 3_001);
+        assert_eq!(min_budget.gas_cost_summary, cost(2_500));
+    }
+
+    #[tokio::test]
+    async fn stays_within_precision() {
+        let node = FakeNode::new().with_min_gas_budget(3_001, cost(2_500));
+
+        let min_budget = search(&node, 10_000, 1_000).await.unwrap();
+
+        assert!(min_budget.gas_budget >= 3_001);
+        assert!(min_budget.gas_budget < 3_001 + 1_000);
+    }
+
+    #[tokio::test]
+    async fn bails_when_the_max_budget_is_too_low() {
+        let node = FakeNode::new().with_min_gas_budget(3_001, cost(2_500));
+
+        assert!(search(&node, 3_000, 1).await.is_err());
+        assert_eq!(probed_budgets(&node), vec![3_000]);
+    }
+
+    #[tokio::test]
+    async fn aborts_on_failures_other_than_gas() {
+        let node = FakeNode::new().with_dry_run_handler(|tx| {
+            let status = if tx.gas_budget() < 3_001 {
+                insufficient_gas_status()
+            } else {
+                SuiExecutionStatus::Failure {
+                    error: "MoveAbort(MoveLocation { .. }, 0) in command 0".to_string(),
+                }
+            };
+            Ok(dry_run_response(status, &cost(2_500)))
+        });
+
+        assert!(search(&node, 10_000, 1).await.is_err());
+        assert_eq!(probed_budgets(&node), vec![10_000]);
+    }
+
+    #[tokio::test]
+    async fn budgets_rejected_by_the_node_count_as_too_low() {
+        let node = FakeNode::new().with_dry_run_handler(|tx| {
+            if tx.gas_budget() < 5 {
+                return Err(Error::Node(format!(
+                    "Error checking transaction input objects: GasBudgetTooLow {{ gas_budget: {}, min_budget: 5 }}",
+                    tx.gas_budget()
+                )));
+            }
+            Ok(dry_run_response(SuiExecutionStatus::Success, &cost(3)))
+        });
+
+        assert_eq!(search(&node, 16, 1).await.unwrap().gas_budget, 5);
+    }
+
+    #[tokio::test]
+    async fn other_node_errors_abort_the_search() {
+        let node = FakeNode::new().with_dry_run_handler(|_| {
+            Err(Error::Node(
+                "Could not find the referenced object".to_string(),
+            ))
+        });
+
+        assert!(search(&node, 16, 1).await.is_err());
+        assert_eq!(probed_budgets(&node), vec![16]);
+    }
+
+    #[tokio::test]
+    async fn traces_every_probe_in_order() {
+        let node = FakeNode::new().with_min_gas_budget(5, cost(3));
+
+        let min_budget = search(&node, 16, 1).await.unwrap();
+
+        let trace: Vec<_> = min_budget
+            .trace
+            .iter()
+            .map(|probe| (probe.gas_budget, probe.gas_cost_summary.is_some()))
+            .collect();
+        assert_eq!(
+            trace,
+            vec![(16, true), (8, true), (4, false), (6, true), (5, true)]
+        );
+        assert_eq!(probed_budgets(&node), vec![16, 8, 4, 6, 5]);
+        assert_eq!(min_budget.gas_budget, 5);
+    }
+}
@@ -0,0 +1,220 @@
+//! Move function descriptors, declared once with `move_fn!` and then called on a `TypedPtb` with
+//! typed arguments.
+
+use std::marker::PhantomData;
+
+use anyhow::{anyhow, ensure};
+use sui_types::{
+    base_types::ObjectID, transaction::Argument, Identifier, TypeTag, SUI_FRAMEWORK_PACKAGE_ID,
+};
+
+use crate::{
+    framework,
+    ptb::{MoveReturn, Res, TypedPtb},
+};
+
+/// Tuples of handles a Move function takes as arguments
+pub trait MoveArgs {
+    fn into_arguments(self) -> Vec<Argument>;
+}
+
+macro_rules! impl_move_args {
+    ($(($($name:ident),*)),* $(,)?) => {
+        $(impl<$($name),*> MoveArgs for ($(Res<$name>,)*) {
+            #[allow(non_snake_case)]
+            fn into_arguments(self) -> Vec<Argument> {
+                let ($($name,)*) = self;
+                vec![$($name.argument()),*]
+            }
+        })*
+    };
+}
+
+impl_move_args!(
+    (),
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F),
+);
+
+/// A Move function along with the Rust mirrors of its parameter and return types
+pub struct MoveFunction<Args, Ret> {
+    package: ObjectID,
+    module: Identifier,
+    function: Identifier,
+    type_arguments: Vec<TypeTag>,
+    _signature: PhantomData<fn(Args) -> Ret>,
+}
+
+impl<Args: MoveArgs, Ret: MoveReturn> MoveFunction<Args, Ret> {
+    /// Functions of the Sui framework are checked against the bundled list of its public
+    /// functions, so that a typo fails here rather than at execution.
+    pub fn new(
+        package: ObjectID,
+        module: &str,
+        function: &str,
+        type_arguments: Vec<TypeTag>,
+    ) -> anyhow::Result<Self> {
+        let descriptor = Self::new_unchecked(package, module, function, type_arguments)?;
+        let (module, function) = (&descriptor.module, &descriptor.function);
+
+        if package == SUI_FRAMEWORK_PACKAGE_ID {
+            let type_parameters =
+                framework::type_parameter_count(module.as_str(), function.as_str())
+                    .ok_or_else(|| anyhow!("No public function 0x2::{}::{}", module, function))?;
+            ensure!(
+                type_parameters == descriptor.type_arguments.len(),
+                "0x2::{}::{} takes {} type arguments, got {}",
+                module,
+                function,
+                type_parameters,
+                descriptor.type_arguments.len()
+            );
+        }
+
+        Ok(descriptor)
+    }
+
+    /// Same as `new` without the check against the bundled framework functions, for descriptors
+    /// known to be right, e.g. generated from the normalized modules of the package.
+    pub fn new_unchecked(
+        package: ObjectID,
+        module: &str,
+        function: &str,
+        type_arguments: Vec<TypeTag>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            package,
+            module: Identifier::new(module)?,
+            function: Identifier::new(function)?,
+            type_arguments,
+            _signature: PhantomData,
+        })
+    }
+
+    pub fn call(&self, pt_builder: &mut TypedPtb, args: Args) -> Ret {
+        pt_builder.move_call(
+            self.package,
+            self.module.clone(),
+            self.function.clone(),
+            self.type_arguments.clone(),
+            args.into_arguments(),
+        )
+    }
+}
+
+impl TypedPtb {
+    pub fn call<Args: MoveArgs, Ret: MoveReturn>(
+        &mut self,
+        function: MoveFunction<Args, Ret>,
+        args: Args,
+    ) -> Ret {
+        function.call(self, args)
+    }
+}
+
+/// Declare a function returning the `MoveFunction` descriptor of a Move function, e.g.
+///
+/// ```ignore
+/// move_fn! {
+///     pub fn split<T>(coin: Coin<T>, amount: u64) -> Coin<T> = SUI_FRAMEWORK_PACKAGE_ID::coin::split;
+/// }
+/// ```
+///
+/// Type parameters are passed as type arguments, in order. Tuple return types yield a tuple of
+/// handles, and a missing return type yields `()`. The declared function fails when
+/// `MoveFunction::new` rejects the descriptor, e.g. for a framework function that does not exist.
+/// Starting the block with `#![unchecked]` goes through `MoveFunction::new_unchecked` instead, as
+/// bindings generated by `move-bindgen` do.
+#[macro_export]
+macro_rules! move_fn {
+    (@build $constructor:ident [$($meta:tt)*] [$($vis:tt)*] $name:ident [$($tp:ident),*]
+        [$($arg:ty),*] [$ret:ty] $package:ident $module:ident $function:ident) => {
+        $($meta)*
+        $($vis)* fn $name<$($tp: $crate::ptb::MoveType),*>(
+        ) -> $crate::__private::anyhow::Result<
+            $crate::move_fn::MoveFunction<($($crate::ptb::Res<$arg>,)*), $ret>,
+        > {
+            $crate::move_fn::MoveFunction::$constructor(
+                $package,
+                stringify!($module),
+                stringify!($function),
+                vec![$(<$tp as $crate::ptb::MoveType>::type_tag()),*],
+            )
+        }
+    };
+    (@items $constructor:ident $(#[$meta:meta])* $vis:vis fn $name:ident $(<$($tp:ident),+>)?
+        ($($param:ident: $arg:ty),* $(,)?) = $package:ident::$module:ident::$function:ident;
+        $($rest:tt)*) => {
+        $crate::move_fn!(@build $constructor [$(#[$meta])*] [$vis] $name [$($($tp),+)?]
+            [$($arg),*] [()] $package $module $function);
+        $crate::move_fn!(@items $constructor $($rest)*);
+    };
+    (@items $constructor:ident $(#[$meta:meta])* $vis:vis fn $name:ident $(<$($tp:ident),+>)?
+        ($($param:ident: $arg:ty),* $(,)?) -> ($($ret:ty),+ $(,)?)
+        = $package:ident::$module:ident::$function:ident; $($rest:tt)*) => {
+        $crate::move_fn!(@build $constructor [$(#[$meta])*] [$vis] $name [$($($tp),+)?]
+            [$($arg),*] [($($crate::ptb::Res<$ret>,)+)] $package $module $function);
+        $crate::move_fn!(@items $constructor $($rest)*);
+    };
+    (@items $constructor:ident $(#[$meta:meta])* $vis:vis fn $name:ident $(<$($tp:ident),+>)?
+        ($($param:ident: $arg:ty),* $(,)?) -> $ret:ty
+        = $package:ident::$module:ident::$function:ident; $($rest:tt)*) => {
+        $crate::move_fn!(@build $constructor [$(#[$meta])*] [$vis] $name [$($($tp),+)?]
+            [$($arg),*] [$crate::ptb::Res<$ret>] $package $module $function);
+        $crate::move_fn!(@items $constructor $($rest)*);
+    };
+    (@items $constructor:ident) => {};
+    (#![unchecked] $($items:tt)*) => {
+        $crate::move_fn!(@items new_unchecked $($items)*);
+    };
+    ($($items:tt)*) => {
+        $crate::move_fn!(@items new $($items)*);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_types::base_types::SuiAddress;
+
+    use super::*;
+    use crate::framework::{Coin, SUI};
+
+    const OTHER_PACKAGE: ObjectID = ObjectID::new([0xab; 32]);
+
+    move_fn! {
+        fn misspelled<T>(coin: Coin<T>) -> u64 = SUI_FRAMEWORK_PACKAGE_ID::coin::valeu;
+        fn missing_type_parameter(coin: Coin<SUI>) -> u64 = SUI_FRAMEWORK_PACKAGE_ID::coin::value;
+        fn checked<T>(coin: Coin<T>) -> u64 = SUI_FRAMEWORK_PACKAGE_ID::coin::value;
+        fn other_package(recipient: SuiAddress) = OTHER_PACKAGE::anything::goes;
+    }
+
+    mod generated {
+        use super::*;
+
+        move_fn! {
+            #![unchecked]
+            pub fn misspelled<T>(coin: Coin<T>) -> u64 = SUI_FRAMEWORK_PACKAGE_ID::coin::valeu;
+        }
+    }
+
+    #[test]
+    fn framework_functions_are_checked() {
+        assert!(misspelled::<SUI>().is_err());
+        assert!(missing_type_parameter().is_err());
+        assert!(checked::<SUI>().is_ok());
+    }
+
+    #[test]
+    fn other_packages_are_not_checked() {
+        assert!(other_package().is_ok());
+    }
+
+    #[test]
+    fn unchecked_descriptors_skip_the_framework_table() {
+        assert!(generated::misspelled::<SUI>().is_ok());
+    }
+}
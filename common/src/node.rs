@@ -0,0 +1,106 @@
+use async_trait::async_trait;
+use sui_sdk::{
+    rpc_types::{
+        CoinPage, DevInspectResults, DryRunTransactionBlockResponse, ObjectsPage,
+        SuiObjectResponseQuery,
+    },
+    SuiClient,
+};
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    transaction::{TransactionData, TransactionKind},
+};
+
+use crate::Result;
+
+/// The subset of the fullnode JSON-RPC API used throughout the cookbook, so that everything can
+/// run against `SuiClient` as well as an in-memory `FakeNode`.
+#[async_trait]
+pub trait NodeApi: Send + Sync {
+    async fn get_owned_objects(
+        &self,
+        owner: SuiAddress,
+        query: Option<SuiObjectResponseQuery>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<ObjectsPage>;
+
+    /// `coin_type` of `None` defaults to `0x2::sui::SUI`
+    async fn get_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: Option<String>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<CoinPage>;
+
+    async fn get_reference_gas_price(&self) -> Result<u64>;
+
+    async fn get_chain_identifier(&self) -> Result<String>;
+
+    async fn dev_inspect_transaction_block(
+        &self,
+        sender: SuiAddress,
+        tx: TransactionKind,
+    ) -> Result<DevInspectResults>;
+
+    async fn dry_run_transaction_block(
+        &self,
+        tx: TransactionData,
+    ) -> Result<DryRunTransactionBlockResponse>;
+}
+
+#[async_trait]
+impl NodeApi for SuiClient {
+    async fn get_owned_objects(
+        &self,
+        owner: SuiAddress,
+        query: Option<SuiObjectResponseQuery>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<ObjectsPage> {
+        Ok(self
+            .read_api()
+            .get_owned_objects(owner, query, cursor, limit)
+            .await?)
+    }
+
+    async fn get_coins(
+        &self,
+        owner: SuiAddress,
+        coin_type: Option<String>,
+        cursor: Option<ObjectID>,
+        limit: Option<usize>,
+    ) -> Result<CoinPage> {
+        Ok(self
+            .coin_read_api()
+            .get_coins(owner, coin_type, cursor, limit)
+            .await?)
+    }
+
+    async fn get_reference_gas_price(&self) -> Result<u64> {
+        Ok(self.read_api().get_reference_gas_price().await?)
+    }
+
+    async fn get_chain_identifier(&self) -> Result<String> {
+        Ok(self.read_api().get_chain_identifier().await?)
+    }
+
+    async fn dev_inspect_transaction_block(
+        &self,
+        sender: SuiAddress,
+        tx: TransactionKind,
+    ) -> Result<DevInspectResults> {
+        Ok(self
+            .read_api()
+            .dev_inspect_transaction_block(sender, tx, None, None)
+            .await?)
+    }
+
+    async fn dry_run_transaction_block(
+        &self,
+        tx: TransactionData,
+    ) -> Result<DryRunTransactionBlockResponse> {
+        Ok(self.read_api().dry_run_transaction_block(tx).await?)
+    }
+}
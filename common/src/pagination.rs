@@ -0,0 +1,88 @@
+use futures::{stream, Stream, TryStreamExt};
+use sui_sdk::rpc_types::{Coin, SuiObjectData, SuiObjectResponseQuery};
+use sui_types::base_types::SuiAddress;
+
+use crate::{NodeApi, Result};
+
+/// Lazily page through the objects owned by `owner` matching `query`, one page at a time.
+///
+/// A page is only requested once the previous one has been consumed, so dropping the stream (or
+/// using combinators like `take` / `try_take_while`) stops further RPC calls. `page_size` of
+/// `None` leaves the page size to the node.
+pub fn owned_object_pages_stream(
+    rpc_client: &impl NodeApi,
+    owner: SuiAddress,
+    query: SuiObjectResponseQuery,
+    page_size: Option<usize>,
+) -> impl Stream<Item = Result<Vec<SuiObjectData>>> + '_ {
+    // `None` once the last page has been fetched, otherwise the cursor of the next page
+    let initial_cursor = Some(None);
+
+    stream::try_unfold(initial_cursor, move |cursor| {
+        let query = query.clone();
+        async move {
+            let Some(cursor) = cursor else {
+                return Ok(None);
+            };
+
+            let response = rpc_client
+                .get_owned_objects(owner, Some(query), cursor, page_size)
+                .await?;
+
+            let next_cursor = match (response.has_next_page, response.next_cursor) {
+                (true, Some(next_cursor)) => Some(Some(next_cursor)),
+                _ => None,
+            };
+            let objects = response.data.into_iter().filter_map(|o| o.data).collect();
+
+            Ok(Some((objects, next_cursor)))
+        }
+    })
+}
+
+/// Same as `owned_object_pages_stream`, but yielding objects one by one.
+pub fn owned_objects_stream(
+    rpc_client: &impl NodeApi,
+    owner: SuiAddress,
+    query: SuiObjectResponseQuery,
+    page_size: Option<usize>,
+) -> impl Stream<Item = Result<SuiObjectData>> + '_ {
+    owned_object_pages_stream(rpc_client, owner, query, page_size)
+        .map_ok(|objects| stream::iter(objects.into_iter().map(Ok)))
+        .try_flatten()
+}
+
+/// Lazily page through the coins of `coin_type` owned by `owner`, yielding them one by one. As for
+/// `owned_object_pages_stream`, a page is only requested once the previous one has been consumed.
+/// `coin_type` of `None` stands for `0x2::sui::SUI`.
+pub fn coins_stream(
+    rpc_client: &impl NodeApi,
+    owner: SuiAddress,
+    coin_type: Option<String>,
+    page_size: Option<usize>,
+) -> impl Stream<Item = Result<Coin>> + '_ {
+    // `None` once the last page has been fetched, otherwise the cursor of the next page
+    let initial_cursor = Some(None);
+
+    stream::try_unfold(initial_cursor, move |cursor| {
+        let coin_type = coin_type.clone();
+        async move {
+            let Some(cursor) = cursor else {
+                return Ok(None);
+            };
+
+            let response = rpc_client
+                .get_coins(owner, coin_type, cursor, page_size)
+                .await?;
+
+            let next_cursor = match (response.has_next_page, response.next_cursor) {
+                (true, Some(next_cursor)) => Some(Some(next_cursor)),
+                _ => None,
+            };
+
+            Ok(Some((response.data, next_cursor)))
+        }
+    })
+    .map_ok(|coins| stream::iter(coins.into_iter().map(Ok)))
+    .try_flatten()
+}
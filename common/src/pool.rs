@@ -0,0 +1,362 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail};
+use sui_sdk::rpc_types::{SuiTransactionBlockEffects, SuiTransactionBlockEffectsAPI};
+use sui_types::base_types::{ObjectID, ObjectRef, SuiAddress};
+
+use crate::{fetch_sorted_gas_coin_refs, limits::MAX_GAS_PAYMENT_OBJECTS, NodeApi};
+
+/// Coins reserved from a `GasPool` for a single transaction, to be handed back with
+/// `GasPool::release` once the transaction is done.
+#[derive(Debug)]
+pub struct GasLease {
+    id: u64,
+    coins: Vec<(ObjectRef, u64)>,
+}
+
+impl GasLease {
+    pub fn gas_payment(&self) -> Vec<ObjectRef> {
+        self.coins.iter().map(|(coin, _)| *coin).collect()
+    }
+
+    pub fn balance(&self) -> u64 {
+        self.coins.iter().map(|(_, balance)| balance).sum()
+    }
+}
+
+struct Reservation {
+    coins: Vec<(ObjectRef, u64)>,
+    expires_at: Instant,
+}
+
+#[derive(Default)]
+struct PoolState {
+    available: Vec<(ObjectRef, u64)>,
+    reserved: HashMap<u64, Reservation>,
+    next_lease_id: u64,
+    /// Set when the local view can no longer be trusted, e.g. after an expired lease
+    stale: bool,
+    /// Coins smashed into the gas coin of a released lease, or emptied by it, which a snapshot of
+    /// the node taken before the release may still list
+    retired: HashSet<ObjectID>,
+}
+
+/// Owns the gas coins of a sender and hands them out to concurrent tasks, so that no two
+/// transactions in flight ever lock the same coin. Runs against any `NodeApi`, e.g. a `FakeNode`.
+pub struct GasPool<C> {
+    client: C,
+    owner: SuiAddress,
+    lease_duration: Duration,
+    state: Mutex<PoolState>,
+}
+
+impl<C: NodeApi> GasPool<C> {
+    pub async fn new(
+        client: C,
+        owner: SuiAddress,
+        lease_duration: Duration,
+    ) -> anyhow::Result<Self> {
+        let pool = Self {
+            client,
+            owner,
+            lease_duration,
+            state: Mutex::new(PoolState::default()),
+        };
+        pool.resync().await?;
+        Ok(pool)
+    }
+
+    /// Reload every coin not currently leased from the node. Leases may be released while the
+    /// coins are being fetched, so coins known locally at a newer version than the node returned
+    /// are kept as they are.
+    pub async fn resync(&self) -> anyhow::Result<()> {
+        let coins = fetch_sorted_gas_coin_refs(&self.client, &self.owner).await?;
+
+        let mut state = self.state.lock().unwrap();
+        let reserved_ids: HashSet<ObjectID> = state
+            .reserved
+            .values()
+            .flat_map(|reservation| reservation.coins.iter().map(|((id, _, _), _)| *id))
+            .collect();
+        let local: HashMap<ObjectID, (ObjectRef, u64)> = state
+            .available
+            .iter()
+            .map(|coin @ ((id, _, _), _)| (*id, *coin))
+            .collect();
+
+        // Once the node stops listing a retired coin, no later snapshot can list it again
+        let listed: HashSet<ObjectID> = coins.iter().map(|((id, _, _), _)| *id).collect();
+        state.retired.retain(|id| listed.contains(id));
+
+        let available = coins
+            .into_iter()
+            .filter(|((id, _, _), _)| !reserved_ids.contains(id) && !state.retired.contains(id))
+            .map(|coin @ ((id, version, _), _)| match local.get(&id) {
+                Some(newer @ ((_, local_version, _), _)) if *local_version > version => *newer,
+                _ => coin,
+            })
+            .collect();
+        state.available = available;
+        state.available.sort_by(|(_, a), (_, b)| b.cmp(a));
+        state.stale = false;
+
+        Ok(())
+    }
+
+    /// Reserve the richest available coins covering `budget`.
+    pub async fn reserve(&self, budget: u64) -> anyhow::Result<GasLease> {
+        if self.reclaim_expired() {
+            self.resync().await?;
+        }
+
+        let mut state = self.state.lock().unwrap();
+
+        let mut total = 0u128;
+        let mut count = 0;
+        for (_, balance) in &state.available {
+            if total >= budget as u128 && count > 0 {
+                break;
+            }
+            total += *balance as u128;
+            count += 1;
+        }
+        if count == 0 || total < budget as u128 {
+            bail!("Not enough available gas to cover a budget of {}", budget);
+        }
+        if count > MAX_GAS_PAYMENT_OBJECTS {
+            bail!("Covering a budget of {} needs {} gas coins", budget, count);
+        }
+
+        let coins: Vec<_> = state.available.drain(..count).collect();
+        let id = state.next_lease_id;
+        state.next_lease_id += 1;
+        state.reserved.insert(
+            id,
+            Reservation {
+                coins: coins.clone(),
+                expires_at: Instant::now() + self.lease_duration,
+            },
+        );
+
+        Ok(GasLease { id, coins })
+    }
+
+    /// Hand back the coins of a lease whose transaction was executed, updating them from its
+    /// effects: gas coins get smashed into the first one, whose balance decreases by the net gas
+    /// usage. A PTB also spending `Argument::GasCoin` must use `release_with_balance` instead.
+    pub async fn release(
+        &self,
+        lease: GasLease,
+        effects: &SuiTransactionBlockEffects,
+    ) -> anyhow::Result<()> {
+        let net_gas_usage = effects.gas_cost_summary().net_gas_usage();
+        let balance = (lease.balance() as i128 - net_gas_usage as i128).max(0) as u64;
+        self.release_with_balance(lease, effects, balance).await
+    }
+
+    /// Same as `release` with the remaining balance of the gas coin known by the caller.
+    pub async fn release_with_balance(
+        &self,
+        lease: GasLease,
+        effects: &SuiTransactionBlockEffects,
+        balance: u64,
+    ) -> anyhow::Result<()> {
+        let gas_object = effects.gas_object().reference.to_object_ref();
+        let ((primary_id, primary_version, _), _) = lease
+            .coins
+            .first()
+            .ok_or_else(|| anyhow!("Lease {} holds no coins", lease.id))?;
+
+        let up_to_date = gas_object.0 == *primary_id && gas_object.1 > *primary_version;
+
+        {
+            let mut state = self.state.lock().unwrap();
+            if state.reserved.remove(&lease.id).is_none() {
+                // Lease expired in the meantime, the coins have already been resynced
+                return Ok(());
+            }
+            if up_to_date {
+                state
+                    .retired
+                    .extend(lease.coins[1..].iter().map(|((id, _, _), _)| *id));
+                if balance > 0 {
+                    state.available.push((gas_object, balance));
+                    state.available.sort_by(|(_, a), (_, b)| b.cmp(a));
+                } else {
+                    state.retired.insert(gas_object.0);
+                }
+            }
+        }
+
+        // Effects for another gas coin or an older version: our view of the coins is off
+        if !up_to_date {
+            self.resync().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Hand back the coins of a lease whose transaction was never executed.
+    pub fn release_unused(&self, lease: GasLease) {
+        let mut state = self.state.lock().unwrap();
+        if state.reserved.remove(&lease.id).is_some() {
+            state.available.extend(lease.coins);
+            state.available.sort_by(|(_, a), (_, b)| b.cmp(a));
+        }
+    }
+
+    /// Hand back a lease whose transaction was rejected for using outdated object versions, and
+    /// reload the pool from the node.
+    pub async fn release_stale(&self, lease: GasLease) -> anyhow::Result<()> {
+        self.state.lock().unwrap().reserved.remove(&lease.id);
+        self.resync().await
+    }
+
+    pub fn available_balance(&self) -> u64 {
+        let state = self.state.lock().unwrap();
+        state.available.iter().map(|(_, balance)| balance).sum()
+    }
+
+    /// Drop expired reservations, returning whether a resync is needed to recover their coins
+    fn reclaim_expired(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let before = state.reserved.len();
+        state
+            .reserved
+            .retain(|_, reservation| reservation.expires_at > now);
+        state.stale |= state.reserved.len() != before;
+        state.stale
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_sdk::rpc_types::SuiExecutionStatus;
+    use sui_types::{
+        base_types::{ObjectDigest, SequenceNumber},
+        gas::GasCostSummary,
+    };
+
+    use super::*;
+    use crate::{
+        fake::{effects, object_id, sui_coin},
+        FakeNode,
+    };
+
+    const OWNER: SuiAddress = SuiAddress::ZERO;
+
+    /// Effects of a transaction charging `net_gas_usage` and leaving `gas_coin` at version 2
+    fn executed(gas_coin: ObjectID, net_gas_usage: u64) -> SuiTransactionBlockEffects {
+        let gas_cost_summary = GasCostSummary {
+            computation_cost: net_gas_usage,
+            storage_cost: 0,
+            storage_rebate: 0,
+            non_refundable_storage_fee: 0,
+        };
+        let gas_object = (
+            gas_coin,
+            SequenceNumber::from_u64(2),
+            ObjectDigest::new([0; 32]),
+        );
+        effects(SuiExecutionStatus::Success, &gas_cost_summary, gas_object)
+    }
+
+    async fn pool(balances: &[(u64, u64)], lease_duration: Duration) -> GasPool<FakeNode> {
+        let coins = balances
+            .iter()
+            .map(|(index, balance)| sui_coin(object_id(*index), *balance))
+            .collect();
+        let node = FakeNode::new().with_page_size(2).with_coins(OWNER, coins);
+        GasPool::new(node, OWNER, lease_duration).await.unwrap()
+    }
+
+    fn leased(lease: &GasLease) -> Vec<(ObjectID, u64)> {
+        lease
+            .gas_payment()
+            .iter()
+            .map(|(id, version, _)| (*id, version.value()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reserves_the_richest_coins_covering_the_budget() {
+        let pool = pool(&[(1, 30), (2, 100), (3, 50)], Duration::from_secs(60)).await;
+
+        let lease = pool.reserve(120).await.unwrap();
+
+        assert_eq!(leased(&lease), vec![(object_id(2), 1), (object_id(3), 1)]);
+        assert_eq!(lease.balance(), 150);
+        assert_eq!(pool.available_balance(), 30);
+        assert!(pool.reserve(31).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn unused_leases_are_handed_back() {
+        let pool = pool(&[(1, 30), (2, 100)], Duration::from_secs(60)).await;
+
+        let lease = pool.reserve(100).await.unwrap();
+        pool.release_unused(lease);
+
+        assert_eq!(pool.available_balance(), 130);
+    }
+
+    #[tokio::test]
+    async fn resync_skips_leased_coins() {
+        let pool = pool(&[(1, 30), (2, 100)], Duration::from_secs(60)).await;
+
+        let lease = pool.reserve(100).await.unwrap();
+        pool.resync().await.unwrap();
+
+        assert_eq!(pool.available_balance(), 30);
+        assert_eq!(leased(&lease), vec![(object_id(2), 1)]);
+    }
+
+    #[tokio::test]
+    async fn resync_keeps_coins_released_after_the_snapshot() {
+        let pool = pool(&[(1, 30), (2, 100)], Duration::from_secs(60)).await;
+
+        let lease = pool.reserve(100).await.unwrap();
+        pool.release(lease, &executed(object_id(2), 10))
+            .await
+            .unwrap();
+        // The fake node still returns every coin at version 1, like a lagging snapshot would
+        pool.resync().await.unwrap();
+
+        assert_eq!(pool.available_balance(), 90 + 30);
+        let lease = pool.reserve(80).await.unwrap();
+        assert_eq!(leased(&lease), vec![(object_id(2), 2)]);
+    }
+
+    #[tokio::test]
+    async fn resync_does_not_bring_back_smashed_coins() {
+        let pool = pool(&[(1, 30), (2, 100), (3, 50)], Duration::from_secs(60)).await;
+
+        let lease = pool.reserve(120).await.unwrap();
+        pool.release_with_balance(lease, &executed(object_id(2), 10), 140)
+            .await
+            .unwrap();
+        pool.resync().await.unwrap();
+
+        assert_eq!(pool.available_balance(), 140 + 30);
+        let lease = pool.reserve(170).await.unwrap();
+        assert_eq!(leased(&lease), vec![(object_id(2), 2), (object_id(1), 1)]);
+    }
+
+    #[tokio::test]
+    async fn expired_leases_are_recovered_from_the_node() {
+        let pool = pool(&[(1, 100)], Duration::ZERO).await;
+
+        let expired = pool.reserve(100).await.unwrap();
+        let lease = pool.reserve(100).await.unwrap();
+        assert_eq!(leased(&lease), vec![(object_id(1), 1)]);
+
+        // Releasing the expired lease must not hand its coin out a second time
+        pool.release_unused(expired);
+        assert_eq!(pool.available_balance(), 0);
+    }
+}
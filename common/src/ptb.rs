@@ -0,0 +1,220 @@
+//! Typed wrapper around `ProgrammableTransactionBuilder`, where inputs and command results are
+//! handles carrying the Rust mirror of their Move type, so that e.g. passing a coin where a `u64`
+//! is expected fails to compile.
+
+use std::marker::PhantomData;
+
+use move_core_types::language_storage::StructTag;
+use serde::Serialize;
+use sui_types::{
+    base_types::{ObjectID, SuiAddress},
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{Argument, Command, ObjectArg, ProgrammableTransaction},
+    Identifier, TypeTag,
+};
+
+use crate::framework::{Coin, SUI};
+
+/// Rust types mirroring a Move type
+pub trait MoveType {
+    fn type_tag() -> TypeTag;
+}
+
+/// Rust types mirroring a Move struct, usually through `#[derive(MoveStruct)]`
+pub trait MoveStruct: MoveType {
+    fn struct_tag() -> StructTag;
+}
+
+macro_rules! impl_move_type {
+    ($($rust_type:ty => $type_tag:expr),* $(,)?) => {
+        $(impl MoveType for $rust_type {
+            fn type_tag() -> TypeTag {
+                $type_tag
+            }
+        })*
+    };
+}
+
+impl_move_type!(
+    bool => TypeTag::Bool,
+    u8 => TypeTag::U8,
+    u16 => TypeTag::U16,
+    u32 => TypeTag::U32,
+    u64 => TypeTag::U64,
+    u128 => TypeTag::U128,
+    SuiAddress => TypeTag::Address,
+);
+
+impl<T: MoveType> MoveType for Vec<T> {
+    fn type_tag() -> TypeTag {
+        TypeTag::Vector(Box::new(T::type_tag()))
+    }
+}
+
+/// Handle on a PTB input or command result of Move type `T`
+pub struct Res<T> {
+    argument: Argument,
+    _type: PhantomData<fn() -> T>,
+}
+
+// Derives would require `T: Clone`, while handles are copyable whatever they point to
+impl<T> Clone for Res<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Res<T> {}
+
+impl<T> std::fmt::Debug for Res<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Res<{}>({:?})",
+            std::any::type_name::<T>(),
+            self.argument
+        )
+    }
+}
+
+impl<T> Res<T> {
+    /// Wrap a raw argument, trusting the caller on its type
+    pub fn unchecked(argument: Argument) -> Self {
+        Self {
+            argument,
+            _type: PhantomData,
+        }
+    }
+
+    pub fn argument(&self) -> Argument {
+        self.argument
+    }
+}
+
+/// What a Move call returns, built from the index of its command
+pub trait MoveReturn {
+    fn from_command(command: u16) -> Self;
+}
+
+impl MoveReturn for () {
+    fn from_command(_command: u16) -> Self {}
+}
+
+impl<T> MoveReturn for Res<T> {
+    fn from_command(command: u16) -> Self {
+        Res::unchecked(Argument::Result(command))
+    }
+}
+
+macro_rules! impl_tuple_return {
+    ($(($($name:ident: $index:tt),+)),* $(,)?) => {
+        $(impl<$($name),+> MoveReturn for ($(Res<$name>,)+) {
+            fn from_command(command: u16) -> Self {
+                ($(Res::unchecked(Argument::NestedResult(command, $index)),)+)
+            }
+        })*
+    };
+}
+
+impl_tuple_return!((A: 0, B: 1), (A: 0, B: 1, C: 2), (A: 0, B: 1, C: 2, D: 3));
+
+#[derive(Default)]
+pub struct TypedPtb {
+    builder: ProgrammableTransactionBuilder,
+}
+
+impl TypedPtb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pure<T: MoveType + Serialize>(&mut self, value: T) -> anyhow::Result<Res<T>> {
+        Ok(Res::unchecked(self.builder.pure(value)?))
+    }
+
+    /// Owned or shared object input, the caller vouching for it being a `T`
+    pub fn obj<T: MoveType>(&mut self, object: ObjectArg) -> anyhow::Result<Res<T>> {
+        Ok(Res::unchecked(self.builder.obj(object)?))
+    }
+
+    /// The coin paying for gas
+    pub fn gas_coin(&self) -> Res<Coin<SUI>> {
+        Res::unchecked(Argument::GasCoin)
+    }
+
+    /// Untyped Move call, its arguments and return type being trusted
+    pub fn move_call<R: MoveReturn>(
+        &mut self,
+        package: ObjectID,
+        module: Identifier,
+        function: Identifier,
+        type_arguments: Vec<TypeTag>,
+        arguments: Vec<Argument>,
+    ) -> R {
+        let result = self.builder.programmable_move_call(
+            package,
+            module,
+            function,
+            type_arguments,
+            arguments,
+        );
+        R::from_command(command_index(result))
+    }
+
+    pub fn split_coins<T>(
+        &mut self,
+        coin: Res<Coin<T>>,
+        amounts: &[Res<u64>],
+    ) -> Vec<Res<Coin<T>>> {
+        let result = self.builder.command(Command::SplitCoins(
+            coin.argument(),
+            amounts.iter().map(Res::argument).collect(),
+        ));
+        let command = command_index(result);
+        (0..amounts.len() as u16)
+            .map(|i| Res::unchecked(Argument::NestedResult(command, i)))
+            .collect()
+    }
+
+    pub fn merge_coins<T>(&mut self, destination: Res<Coin<T>>, sources: &[Res<Coin<T>>]) {
+        self.builder.command(Command::MergeCoins(
+            destination.argument(),
+            sources.iter().map(Res::argument).collect(),
+        ));
+    }
+
+    /// Transfer `object` to `recipient`, returning the handle on the recipient input, e.g. to
+    /// label it
+    pub fn transfer<T: MoveType>(
+        &mut self,
+        recipient: SuiAddress,
+        object: Res<T>,
+    ) -> Res<SuiAddress> {
+        let recipient: Res<SuiAddress> = Res::unchecked(
+            self.builder
+                .pure(recipient)
+                .expect("Addresses always serialize"),
+        );
+        self.builder.command(Command::TransferObjects(
+            vec![object.argument()],
+            recipient.argument(),
+        ));
+        recipient
+    }
+
+    /// Escape hatch to the underlying builder
+    pub fn builder_mut(&mut self) -> &mut ProgrammableTransactionBuilder {
+        &mut self.builder
+    }
+
+    pub fn finish(self) -> ProgrammableTransaction {
+        self.builder.finish()
+    }
+}
+
+fn command_index(result: Argument) -> u16 {
+    match result {
+        Argument::Result(command) => command,
+        _ => unreachable!("Commands always return a Result"),
+    }
+}
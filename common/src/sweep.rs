@@ -0,0 +1,219 @@
+use std::str::FromStr;
+
+use anyhow::ensure;
+use futures::TryStreamExt;
+use sui_sdk::rpc_types::{SuiObjectData, SuiTransactionBlockEffectsAPI};
+use sui_types::{
+    base_types::{ObjectRef, SuiAddress},
+    coin,
+    gas::GasCostSummary,
+    programmable_transaction_builder::ProgrammableTransactionBuilder,
+    transaction::{ObjectArg, ProgrammableTransaction, TransactionData},
+    Identifier, TypeTag, SUI_FRAMEWORK_PACKAGE_ID,
+};
+
+use crate::{
+    decode_gas_coin, gas_coins_query, limits::MAX_TX_SIZE_BYTES, merge::MAX_MERGED_INPUTS_PER_TX,
+    owned_objects_stream, NodeApi,
+};
+
+/// Fetch the sender's SUI coins with a zero balance, which `fetch_sorted_gas_coins` leaves out but
+/// still hold a storage deposit.
+pub async fn fetch_empty_gas_coins(
+    rpc_client: &impl NodeApi,
+    sender: &SuiAddress,
+) -> crate::Result<Vec<SuiObjectData>> {
+    let mut empty_coins = vec![];
+    let mut objects = Box::pin(owned_objects_stream(
+        rpc_client,
+        *sender,
+        gas_coins_query(),
+        None,
+    ));
+    while let Some(object) = objects.try_next().await? {
+        if decode_gas_coin(&object)? == 0 {
+            empty_coins.push(object);
+        }
+    }
+
+    Ok(empty_coins)
+}
+
+/// Room left in each sweep transaction for everything but its PTB: sender, gas data with a payment
+/// of a few dozen coins, expiration and signatures
+const NON_PTB_TX_BYTES: usize = 4 * 1024;
+
+/// Build PTBs destroying `empty_coins` with `0x2::coin::destroy_zero`, one command per coin, split
+/// in as many transactions as the size, input and command limits require.
+pub fn build_destroy_zero_ptbs(
+    empty_coins: &[ObjectRef],
+) -> anyhow::Result<Vec<ProgrammableTransaction>> {
+    let destroy_zero_function = Identifier::from_str("destroy_zero")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L223-L228
+    let sui_coin_arg_type = TypeTag::from_str("0x2::sui::SUI")?;
+
+    let build_ptb = |batch: &[ObjectRef]| -> anyhow::Result<ProgrammableTransaction> {
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        for coin in batch {
+            let coin_arg = pt_builder.obj(ObjectArg::ImmOrOwnedObject(*coin))?;
+            pt_builder.programmable_move_call(
+                SUI_FRAMEWORK_PACKAGE_ID,
+                coin::COIN_MODULE_NAME.to_owned(),
+                destroy_zero_function.to_owned(),
+                vec![sui_coin_arg_type.to_owned()],
+                vec![coin_arg],
+            ); // ignore the result, this function returns nothing
+        }
+        Ok(pt_builder.finish())
+    };
+
+    let Some(first_coin) = empty_coins.first() else {
+        return Ok(vec![]);
+    };
+    // Every coin adds an input and a command of the same size, so a single coin PTB gives an upper
+    // bound of the bytes per coin
+    let coin_bytes = bcs::serialized_size(&build_ptb(std::slice::from_ref(first_coin))?)?;
+    let batch_size =
+        ((MAX_TX_SIZE_BYTES - NON_PTB_TX_BYTES) / coin_bytes).min(MAX_MERGED_INPUTS_PER_TX);
+
+    empty_coins.chunks(batch_size).map(build_ptb).collect()
+}
+
+/// Expected outcome of a sweep transaction, read from a dry run
+#[derive(Debug, Clone)]
+pub struct SweepEstimate {
+    pub gas_cost_summary: GasCostSummary,
+}
+
+impl SweepEstimate {
+    pub fn storage_rebate(&self) -> u64 {
+        self.gas_cost_summary.storage_rebate
+    }
+
+    /// Rebate left once computation and storage costs are paid, negative if the sweep costs more
+    /// than it reclaims
+    pub fn net_rebate(&self) -> i128 {
+        self.gas_cost_summary.storage_rebate as i128
+            - self.gas_cost_summary.computation_cost as i128
+            - self.gas_cost_summary.storage_cost as i128
+    }
+}
+
+/// Dry run a sweep PTB to find out the storage rebate it would reclaim.
+pub async fn estimate_sweep_rebate(
+    rpc_client: &impl NodeApi,
+    sender: SuiAddress,
+    pt: ProgrammableTransaction,
+    gas_payment: Vec<ObjectRef>,
+    gas_budget: u64,
+) -> anyhow::Result<SweepEstimate> {
+    let gas_price = rpc_client.get_reference_gas_price().await?;
+    let tx_data = TransactionData::new_programmable(sender, gas_payment, pt, gas_budget, gas_price);
+
+    let response = rpc_client.dry_run_transaction_block(tx_data).await?;
+    ensure!(
+        response.effects.status().is_ok(),
+        "Sweep dry run failed: {:?}",
+        response.effects.status()
+    );
+
+    Ok(SweepEstimate {
+        gas_cost_summary: response.effects.gas_cost_summary().clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use sui_sdk::rpc_types::SuiExecutionStatus;
+    use sui_types::{
+        base_types::{ObjectDigest, SequenceNumber},
+        transaction::TransactionDataAPI,
+    };
+
+    use super::*;
+    use crate::fake::{dry_run_response, insufficient_gas_status, object_id, FakeNode};
+
+    fn coin_ref(index: u64) -> ObjectRef {
+        (
+            object_id(index),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::new([0; 32]),
+        )
+    }
+
+    #[test]
+    fn batches_fit_the_transaction_size_limit() {
+        let empty_coins: Vec<_> = (1..=2_000).map(coin_ref).collect();
+
+        let ptbs = build_destroy_zero_ptbs(&empty_coins).unwrap();
+
+        // ~175 bytes per coin, ~725 coins per transaction
+        assert_eq!(ptbs.len(), 3);
+        assert!(ptbs[0].inputs.len() > 700);
+        assert_eq!(
+            ptbs.iter().map(|pt| pt.inputs.len()).sum::<usize>(),
+            empty_coins.len()
+        );
+        for pt in ptbs {
+            assert_eq!(pt.commands.len(), pt.inputs.len());
+            let tx_data = TransactionData::new_programmable(
+                SuiAddress::ZERO,
+                vec![coin_ref(0)],
+                pt,
+                10_000_000,
+                1_000,
+            );
+            assert!(
+                bcs::serialized_size(&tx_data).unwrap() < MAX_TX_SIZE_BYTES - NON_PTB_TX_BYTES / 2
+            );
+        }
+    }
+
+    #[test]
+    fn no_empty_coins_no_ptb() {
+        assert!(build_destroy_zero_ptbs(&[]).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn rebate_is_read_from_the_dry_run() {
+        let gas_cost_summary = GasCostSummary {
+            computation_cost: 1_000_000,
+            storage_cost: 2_000_000,
+            storage_rebate: 9_000_000,
+            non_refundable_storage_fee: 0,
+        };
+        let node = FakeNode::new().push_dry_run(Ok(dry_run_response(
+            SuiExecutionStatus::Success,
+            &gas_cost_summary,
+        )));
+        let pt = build_destroy_zero_ptbs(&[coin_ref(1), coin_ref(2)])
+            .unwrap()
+            .remove(0);
+
+        let estimate =
+            estimate_sweep_rebate(&node, SuiAddress::ZERO, pt, vec![coin_ref(3)], 5_000_000)
+                .await
+                .unwrap();
+
+        assert_eq!(estimate.storage_rebate(), 9_000_000);
+        assert_eq!(estimate.net_rebate(), 6_000_000);
+        let dry_runs = node.dry_runs();
+        assert_eq!(dry_runs.len(), 1);
+        assert_eq!(dry_runs[0].gas(), [coin_ref(3)]);
+        assert_eq!(dry_runs[0].gas_budget(), 5_000_000);
+        assert_eq!(dry_runs[0].gas_price(), 1_000);
+    }
+
+    #[tokio::test]
+    async fn failed_sweep_dry_runs_are_errors() {
+        let node = FakeNode::new().push_dry_run(Ok(dry_run_response(
+            insufficient_gas_status(),
+            &GasCostSummary::default(),
+        )));
+        let pt = build_destroy_zero_ptbs(&[coin_ref(1)]).unwrap().remove(0);
+
+        let result =
+            estimate_sweep_rebate(&node, SuiAddress::ZERO, pt, vec![coin_ref(3)], 5_000_000).await;
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,11 @@
+use common::{
+    framework::{math, Coin, SUI},
+    Res, TypedPtb,
+};
+
+fn main() {
+    let mut pt_builder = TypedPtb::new();
+    let coin: Res<Coin<SUI>> = pt_builder.gas_coin();
+    let denominator: Res<u64> = pt_builder.pure(9u64).unwrap();
+    let _: Res<u64> = pt_builder.call(math::divide_and_round_up().unwrap(), (coin, denominator));
+}
@@ -0,0 +1,7 @@
+use common::{framework::math, Res, TypedPtb};
+
+fn main() {
+    let mut pt_builder = TypedPtb::new();
+    let numerator: Res<u64> = pt_builder.pure(10u64).unwrap();
+    let _: Res<u64> = pt_builder.call(math::divide_and_round_up().unwrap(), (numerator,));
+}
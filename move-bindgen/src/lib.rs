@@ -0,0 +1,539 @@
+//! Rust bindings for Move packages, generated from their normalized modules as returned by
+//! `sui_getNormalizedMoveModulesByPackage`.
+//!
+//! Every Move struct gets a `#[derive(MoveStruct)]` mirror, and every function callable from a PTB
+//! a `move_fn!` descriptor. Items that cannot be expressed (e.g. `u256` values or structs of
+//! packages outside the input) are left out with a comment saying why.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    path::Path,
+};
+
+use anyhow::{bail, ensure, Context};
+use sui_sdk::rpc_types::{
+    SuiMoveNormalizedFunction, SuiMoveNormalizedModule, SuiMoveNormalizedStruct,
+    SuiMoveNormalizedType, SuiMoveVisibility,
+};
+
+/// Most arguments a `MoveArgs` tuple takes
+const MAX_PARAMETERS: usize = 6;
+/// Most results a `MoveReturn` tuple takes
+const MAX_RETURN_VALUES: usize = 4;
+
+/// Generated source, along with the items that were left out of it
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    pub source: String,
+    pub skipped: Vec<String>,
+}
+
+/// Read normalized modules from JSON files, each holding either a single module or a map of
+/// module name to module as returned for a whole package.
+pub fn read_modules(
+    paths: impl IntoIterator<Item = impl AsRef<Path>>,
+) -> anyhow::Result<Vec<SuiMoveNormalizedModule>> {
+    let mut modules = vec![];
+    for path in paths {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let json: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+
+        if json.get("exposedFunctions").is_some() {
+            modules.push(serde_json::from_value(json)?);
+        } else {
+            let package: BTreeMap<String, SuiMoveNormalizedModule> =
+                serde_json::from_value(json)
+                    .with_context(|| format!("{} is not a normalized module", path.display()))?;
+            modules.extend(package.into_values());
+        }
+    }
+    Ok(modules)
+}
+
+/// Generate the bindings of `modules`, which must all belong to the same package.
+pub fn generate(modules: &[SuiMoveNormalizedModule]) -> anyhow::Result<Bindings> {
+    let Some(first) = modules.first() else {
+        bail!("No module to generate bindings for");
+    };
+    let package = short_address(&first.address);
+    for module in modules {
+        ensure!(
+            short_address(&module.address) == package,
+            "Modules of several packages given: {} and {}",
+            package,
+            module.address
+        );
+    }
+
+    let mut modules: Vec<&SuiMoveNormalizedModule> = modules.iter().collect();
+    modules.sort_by(|a, b| a.name.cmp(&b.name));
+    modules.dedup_by(|a, b| a.name == b.name);
+
+    let mut generator = Generator {
+        package: package.clone(),
+        structs: BTreeSet::new(),
+        skipped: vec![],
+    };
+    generator.resolve_structs(&modules);
+
+    let mut source = String::new();
+    writeln!(
+        source,
+        "// @generated by move-bindgen from the normalized modules of {}, do not edit",
+        package
+    )?;
+    writeln!(source)?;
+    writeln!(
+        source,
+        "pub const PACKAGE_ID: ::sui_types::base_types::ObjectID = {};",
+        object_id_literal(&package)?
+    )?;
+
+    for module in &modules {
+        writeln!(source)?;
+        generator.module(&mut source, module)?;
+    }
+
+    Ok(Bindings {
+        source,
+        skipped: generator.skipped,
+    })
+}
+
+struct Generator {
+    package: String,
+    /// `(module, struct)` pairs with a generated mirror
+    structs: BTreeSet<(String, String)>,
+    skipped: Vec<String>,
+}
+
+impl Generator {
+    /// Find the structs that can be mirrored, dropping those whose fields refer to a struct that
+    /// cannot be until none is left
+    fn resolve_structs(&mut self, modules: &[&SuiMoveNormalizedModule]) {
+        self.structs = modules
+            .iter()
+            .flat_map(|module| {
+                module
+                    .structs
+                    .keys()
+                    .filter(|name| !is_keyword(name))
+                    .map(|name| (module.name.clone(), name.clone()))
+            })
+            .collect();
+
+        loop {
+            let unsupported: Vec<(String, String)> = modules
+                .iter()
+                .flat_map(|module| {
+                    module
+                        .structs
+                        .iter()
+                        .map(move |(name, struct_)| (module, name, struct_))
+                })
+                .filter(|(module, name, struct_)| {
+                    self.structs
+                        .contains(&(module.name.clone(), name.to_string()))
+                        && self.struct_fields(&module.name, struct_).is_err()
+                })
+                .map(|(module, name, _)| (module.name.clone(), name.clone()))
+                .collect();
+            if unsupported.is_empty() {
+                break;
+            }
+            for key in unsupported {
+                self.structs.remove(&key);
+            }
+        }
+    }
+
+    fn module(&mut self, out: &mut String, module: &SuiMoveNormalizedModule) -> anyhow::Result<()> {
+        if is_keyword(&module.name) {
+            self.skip(out, "", &module.name, "module name is a Rust keyword")?;
+            return Ok(());
+        }
+
+        writeln!(out, "pub mod {} {{", module.name)?;
+
+        for (name, struct_) in &module.structs {
+            self.struct_(out, &module.name, name, struct_)?;
+        }
+
+        let mut functions = String::new();
+        for (name, function) in &module.exposed_functions {
+            self.function(&mut functions, &module.name, name, function)?;
+        }
+        if !functions.is_empty() {
+            writeln!(out, "    use super::PACKAGE_ID;")?;
+            writeln!(out)?;
+            writeln!(out, "    ::common::move_fn! {{")?;
+            // The signatures come from the package itself, the framework table is only a
+            // hand-written subset of it
+            writeln!(out, "        #![unchecked]")?;
+            out.push_str(&functions);
+            writeln!(out, "    }}")?;
+        }
+
+        writeln!(out, "}}")?;
+        Ok(())
+    }
+
+    fn struct_(
+        &mut self,
+        out: &mut String,
+        module: &str,
+        name: &str,
+        struct_: &SuiMoveNormalizedStruct,
+    ) -> anyhow::Result<()> {
+        if !self
+            .structs
+            .contains(&(module.to_string(), name.to_string()))
+        {
+            let reason = match self.struct_fields(module, struct_) {
+                Err(reason) => reason,
+                Ok(_) if is_keyword(name) => "name is a Rust keyword".to_string(),
+                Ok(_) => "a field refers to a struct that was skipped".to_string(),
+            };
+            return self.skip(out, module, name, &reason);
+        }
+        let fields = self
+            .struct_fields(module, struct_)
+            .expect("Resolved structs have supported fields");
+
+        let type_params = type_parameter_names(struct_.type_parameters.len());
+        writeln!(out, "    /// `{}::{}::{}`", self.package, module, name)?;
+        writeln!(out, "    #[derive(Debug, ::common::MoveStruct)]")?;
+        writeln!(
+            out,
+            "    #[move_struct(package = \"{}\", module = \"{}\", name = \"{}\")]",
+            self.package, module, name
+        )?;
+        writeln!(out, "    pub struct {}{} {{", name, generics(&type_params))?;
+        for (field_name, type_) in fields {
+            if is_keyword(&field_name) {
+                writeln!(out, "        #[move_struct(rename = \"{}\")]", field_name)?;
+                writeln!(out, "        pub {}_: {},", field_name, type_)?;
+            } else {
+                writeln!(out, "        pub {}: {},", field_name, type_)?;
+            }
+        }
+        if !type_params.is_empty() {
+            writeln!(out, "        #[move_struct(skip)]")?;
+            writeln!(
+                out,
+                "        pub _phantom: ::std::marker::PhantomData<fn() -> ({},)>,",
+                type_params.join(", ")
+            )?;
+        }
+        writeln!(out, "    }}")?;
+        writeln!(out)?;
+        Ok(())
+    }
+
+    fn struct_fields(
+        &self,
+        module: &str,
+        struct_: &SuiMoveNormalizedStruct,
+    ) -> Result<Vec<(String, String)>, String> {
+        struct_
+            .fields
+            .iter()
+            .map(|field| Ok((field.name.clone(), self.rust_type(module, &field.type_)?)))
+            .collect()
+    }
+
+    fn function(
+        &mut self,
+        out: &mut String,
+        module: &str,
+        name: &str,
+        function: &SuiMoveNormalizedFunction,
+    ) -> anyhow::Result<()> {
+        // Friend functions cannot be called from a PTB, unless they are entry functions
+        if !matches!(function.visibility, SuiMoveVisibility::Public) && !function.is_entry {
+            return Ok(());
+        }
+
+        match self.signature(module, name, function) {
+            Ok((parameters, return_)) => {
+                let type_params = type_parameter_names(function.type_parameters.len());
+                writeln!(out, "        /// `{}::{}::{}`", self.package, module, name)?;
+                writeln!(
+                    out,
+                    "        pub fn {}{}({}){} = PACKAGE_ID::{}::{};",
+                    name,
+                    generics(&type_params),
+                    parameters
+                        .iter()
+                        .enumerate()
+                        .map(|(i, type_)| format!("arg{}: {}", i, type_))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    match return_.len() {
+                        0 => String::new(),
+                        1 => format!(" -> {}", return_[0]),
+                        _ => format!(" -> ({})", return_.join(", ")),
+                    },
+                    module,
+                    name
+                )?;
+                Ok(())
+            }
+            Err(reason) => self.skip(out, module, name, &reason),
+        }
+    }
+
+    fn signature(
+        &self,
+        module: &str,
+        name: &str,
+        function: &SuiMoveNormalizedFunction,
+    ) -> Result<(Vec<String>, Vec<String>), String> {
+        if is_keyword(name) {
+            return Err("name is a Rust keyword".to_string());
+        }
+
+        let mut parameters = vec![];
+        for type_ in &function.parameters {
+            let type_ = match type_ {
+                SuiMoveNormalizedType::Reference(inner)
+                | SuiMoveNormalizedType::MutableReference(inner) => inner,
+                type_ => type_,
+            };
+            // The `TxContext` is provided by the runtime rather than passed by the PTB
+            if is_tx_context(type_) {
+                continue;
+            }
+            parameters.push(self.rust_type(module, type_)?);
+        }
+        if parameters.len() > MAX_PARAMETERS {
+            return Err(format!(
+                "{} parameters, at most {} are supported",
+                parameters.len(),
+                MAX_PARAMETERS
+            ));
+        }
+
+        let return_ = function
+            .return_
+            .iter()
+            .map(|type_| match type_ {
+                SuiMoveNormalizedType::Reference(_)
+                | SuiMoveNormalizedType::MutableReference(_) => {
+                    Err("returns a reference, which a PTB cannot use".to_string())
+                }
+                type_ => self.rust_type(module, type_),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if return_.len() > MAX_RETURN_VALUES {
+            return Err(format!(
+                "{} return values, at most {} are supported",
+                return_.len(),
+                MAX_RETURN_VALUES
+            ));
+        }
+
+        Ok((parameters, return_))
+    }
+
+    /// Rust type for `type_` as seen from `module`, or why there is none
+    fn rust_type(&self, module: &str, type_: &SuiMoveNormalizedType) -> Result<String, String> {
+        Ok(match type_ {
+            SuiMoveNormalizedType::Bool => "bool".to_string(),
+            SuiMoveNormalizedType::U8 => "u8".to_string(),
+            SuiMoveNormalizedType::U16 => "u16".to_string(),
+            SuiMoveNormalizedType::U32 => "u32".to_string(),
+            SuiMoveNormalizedType::U64 => "u64".to_string(),
+            SuiMoveNormalizedType::U128 => "u128".to_string(),
+            SuiMoveNormalizedType::U256 => return Err("u256 has no Rust mirror".to_string()),
+            SuiMoveNormalizedType::Address => "::sui_types::base_types::SuiAddress".to_string(),
+            SuiMoveNormalizedType::Signer => {
+                return Err("signer cannot be passed by a PTB".to_string())
+            }
+            SuiMoveNormalizedType::Vector(inner) => {
+                format!("::std::vec::Vec<{}>", self.rust_type(module, inner)?)
+            }
+            SuiMoveNormalizedType::TypeParameter(index) => format!("T{}", index),
+            SuiMoveNormalizedType::Reference(_) | SuiMoveNormalizedType::MutableReference(_) => {
+                return Err("references cannot be stored".to_string())
+            }
+            SuiMoveNormalizedType::Struct {
+                address,
+                module: struct_module,
+                name,
+                type_arguments,
+            } => {
+                let type_arguments = type_arguments
+                    .iter()
+                    .map(|type_| self.rust_type(module, type_))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let address = short_address(address);
+
+                if address == self.package
+                    && self
+                        .structs
+                        .contains(&(struct_module.clone(), name.clone()))
+                {
+                    let path = if struct_module == module {
+                        name.clone()
+                    } else {
+                        format!("super::{}::{}", struct_module, name)
+                    };
+                    format!("{}{}", path, generics(&type_arguments))
+                } else {
+                    well_known_type(&address, struct_module, name, &type_arguments).ok_or_else(
+                        || format!("no mirror of {}::{}::{}", address, struct_module, name),
+                    )?
+                }
+            }
+        })
+    }
+
+    fn skip(
+        &mut self,
+        out: &mut String,
+        module: &str,
+        name: &str,
+        reason: &str,
+    ) -> anyhow::Result<()> {
+        let item = if module.is_empty() {
+            format!("{}::{}", self.package, name)
+        } else {
+            format!("{}::{}::{}", self.package, module, name)
+        };
+        writeln!(out, "    // Skipped `{}`: {}", item, reason)?;
+        self.skipped.push(format!("{}: {}", item, reason));
+        Ok(())
+    }
+}
+
+/// Mirrors of standard library and framework types, used when they are not part of the input
+fn well_known_type(
+    address: &str,
+    module: &str,
+    name: &str,
+    type_arguments: &[String],
+) -> Option<String> {
+    Some(match (address, module, name, type_arguments) {
+        ("0x1", "string", "String", []) | ("0x1", "ascii", "String", []) => {
+            "::std::string::String".to_string()
+        }
+        ("0x1", "option", "Option", [inner]) => format!("::std::option::Option<{}>", inner),
+        ("0x2", "object", "UID", []) => "::sui_types::id::UID".to_string(),
+        ("0x2", "object", "ID", []) => "::sui_types::id::ID".to_string(),
+        // The balance layout does not depend on the coin type
+        ("0x2", "balance", "Balance", [_]) => "::sui_types::balance::Balance".to_string(),
+        ("0x2", "coin", "Coin", [inner]) => format!("::common::framework::Coin<{}>", inner),
+        ("0x2", "sui", "SUI", []) => "::common::framework::SUI".to_string(),
+        ("0x2", "kiosk", "Kiosk", []) => "::common::framework::Kiosk".to_string(),
+        ("0x2", "kiosk", "KioskOwnerCap", []) => "::common::framework::KioskOwnerCap".to_string(),
+        _ => return None,
+    })
+}
+
+fn is_tx_context(type_: &SuiMoveNormalizedType) -> bool {
+    matches!(
+        type_,
+        SuiMoveNormalizedType::Struct { address, module, name, .. }
+            if short_address(address) == "0x2" && module == "tx_context" && name == "TxContext"
+    )
+}
+
+/// `0x0000..02` to `0x2`
+fn short_address(address: &str) -> String {
+    let hex = address.trim_start_matches("0x").trim_start_matches('0');
+    if hex.is_empty() {
+        "0x0".to_string()
+    } else {
+        format!("0x{}", hex.to_lowercase())
+    }
+}
+
+fn object_id_literal(address: &str) -> anyhow::Result<String> {
+    let hex = address.trim_start_matches("0x");
+    ensure!(
+        hex.len() <= 64 && hex.chars().all(|c| c.is_ascii_hexdigit()),
+        "{} is not a package address",
+        address
+    );
+    let hex = format!("{:0>64}", hex);
+    let bytes = (0..32)
+        .map(|i| format!("0x{}", &hex[2 * i..2 * i + 2]))
+        .collect::<Vec<_>>();
+    Ok(format!(
+        "::sui_types::base_types::ObjectID::new([{}])",
+        bytes.join(", ")
+    ))
+}
+
+fn type_parameter_names(count: usize) -> Vec<String> {
+    (0..count).map(|i| format!("T{}", i)).collect()
+}
+
+fn generics(types: &[String]) -> String {
+    if types.is_empty() {
+        String::new()
+    } else {
+        format!("<{}>", types.join(", "))
+    }
+}
+
+/// Rust keywords that Move accepts as identifiers
+fn is_keyword(identifier: &str) -> bool {
+    matches!(
+        identifier,
+        "as" | "async"
+            | "await"
+            | "box"
+            | "break"
+            | "const"
+            | "continue"
+            | "crate"
+            | "dyn"
+            | "else"
+            | "enum"
+            | "extern"
+            | "false"
+            | "fn"
+            | "for"
+            | "if"
+            | "impl"
+            | "in"
+            | "loop"
+            | "match"
+            | "mod"
+            | "move"
+            | "mut"
+            | "priv"
+            | "pub"
+            | "ref"
+            | "return"
+            | "self"
+            | "Self"
+            | "static"
+            | "struct"
+            | "super"
+            | "trait"
+            | "true"
+            | "type"
+            | "typeof"
+            | "unsafe"
+            | "unsized"
+            | "use"
+            | "virtual"
+            | "where"
+            | "while"
+            | "yield"
+            | "abstract"
+            | "become"
+            | "do"
+            | "final"
+            | "macro"
+            | "override"
+            | "try"
+    )
+}
@@ -0,0 +1,81 @@
+use std::path::PathBuf;
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use common::Network;
+use move_bindgen::{generate, read_modules};
+use sui_sdk::SuiClientBuilder;
+use sui_types::base_types::ObjectID;
+
+/// Generate Rust bindings from normalized Move modules
+#[derive(Debug, Parser)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Save the normalized modules of a package, one `<module>.json` file each
+    Fetch {
+        #[arg(long)]
+        package: ObjectID,
+        /// Only save these modules, all of them by default
+        #[arg(long = "module")]
+        modules: Vec<String>,
+        #[arg(long, env = "SUI_NETWORK", default_value = "mainnet")]
+        network: Network,
+        #[arg(long)]
+        out_dir: PathBuf,
+    },
+    /// Generate the bindings of normalized modules saved by `fetch` or straight from the RPC
+    Generate {
+        /// Files holding a module, or all the modules of a package
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+        #[arg(long)]
+        out: PathBuf,
+    },
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    match Cli::parse().command {
+        Command::Fetch {
+            package,
+            modules,
+            network,
+            out_dir,
+        } => {
+            let rpc_client = SuiClientBuilder::default().build(network.rpc_url()).await?;
+            let normalized = rpc_client
+                .read_api()
+                .get_normalized_move_modules_by_package(package)
+                .await?;
+
+            std::fs::create_dir_all(&out_dir)?;
+            for (name, module) in normalized {
+                if !modules.is_empty() && !modules.contains(&name) {
+                    continue;
+                }
+                let path = out_dir.join(format!("{}.json", name));
+                std::fs::write(&path, serde_json::to_string_pretty(&module)?)
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                println!("Saved {}", path.display());
+            }
+        }
+        Command::Generate { inputs, out } => {
+            let modules = read_modules(&inputs)?;
+            let bindings = generate(&modules)?;
+
+            for skipped in &bindings.skipped {
+                eprintln!("Skipped {}", skipped);
+            }
+            std::fs::write(&out, bindings.source)
+                .with_context(|| format!("Failed to write {}", out.display()))?;
+            println!("Generated {} from {} modules", out.display(), modules.len());
+        }
+    }
+
+    Ok(())
+}
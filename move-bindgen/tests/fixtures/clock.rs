@@ -0,0 +1,21 @@
+// @generated by move-bindgen from the normalized modules of 0x2, do not edit
+
+pub const PACKAGE_ID: ::sui_types::base_types::ObjectID = ::sui_types::base_types::ObjectID::new([0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02]);
+
+pub mod clock {
+    /// `0x2::clock::Clock`
+    #[derive(Debug, ::common::MoveStruct)]
+    #[move_struct(package = "0x2", module = "clock", name = "Clock")]
+    pub struct Clock {
+        pub id: ::sui_types::id::UID,
+        pub timestamp_ms: u64,
+    }
+
+    use super::PACKAGE_ID;
+
+    ::common::move_fn! {
+        #![unchecked]
+        /// `0x2::clock::timestamp_ms`
+        pub fn timestamp_ms(arg0: Clock) -> u64 = PACKAGE_ID::clock::timestamp_ms;
+    }
+}
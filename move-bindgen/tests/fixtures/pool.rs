@@ -0,0 +1,59 @@
+// @generated by move-bindgen from the normalized modules of 0xabc, do not edit
+
+pub const PACKAGE_ID: ::sui_types::base_types::ObjectID = ::sui_types::base_types::ObjectID::new([0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x0a, 0xbc]);
+
+pub mod pool {
+    /// `0xabc::pool::Pool`
+    #[derive(Debug, ::common::MoveStruct)]
+    #[move_struct(package = "0xabc", module = "pool", name = "Pool")]
+    pub struct Pool<T0> {
+        pub id: ::sui_types::id::UID,
+        pub balance: ::sui_types::balance::Balance,
+        pub fee_bps: u64,
+        #[move_struct(skip)]
+        pub _phantom: ::std::marker::PhantomData<fn() -> (T0,)>,
+    }
+
+    /// `0xabc::pool::Receipt`
+    #[derive(Debug, ::common::MoveStruct)]
+    #[move_struct(package = "0xabc", module = "pool", name = "Receipt")]
+    pub struct Receipt {
+        pub pool_id: ::sui_types::id::ID,
+        pub amount: u64,
+    }
+
+    use super::PACKAGE_ID;
+
+    ::common::move_fn! {
+        #![unchecked]
+    // Skipped `0xabc::pool::balance`: returns a reference, which a PTB cannot use
+        /// `0xabc::pool::deposit`
+        pub fn deposit<T0>(arg0: Pool<T0>, arg1: ::common::framework::Coin<T0>) -> Receipt = PACKAGE_ID::pool::deposit;
+        /// `0xabc::pool::fee_bps`
+        pub fn fee_bps<T0>(arg0: Pool<T0>) -> u64 = PACKAGE_ID::pool::fee_bps;
+        /// `0xabc::pool::new`
+        pub fn new<T0>(arg0: u64) -> Pool<T0> = PACKAGE_ID::pool::new;
+        /// `0xabc::pool::set_fee`
+        pub fn set_fee<T0>(arg0: Pool<T0>, arg1: u64) = PACKAGE_ID::pool::set_fee;
+    // Skipped `0xabc::pool::total`: u256 has no Rust mirror
+    // Skipped `0xabc::pool::type`: name is a Rust keyword
+    }
+}
+
+pub mod registry {
+    /// `0xabc::registry::Registry`
+    #[derive(Debug, ::common::MoveStruct)]
+    #[move_struct(package = "0xabc", module = "registry", name = "Registry")]
+    pub struct Registry {
+        pub id: ::sui_types::id::UID,
+        pub pools: ::std::vec::Vec<::sui_types::id::ID>,
+    }
+
+    use super::PACKAGE_ID;
+
+    ::common::move_fn! {
+        #![unchecked]
+        /// `0xabc::registry::register`
+        pub fn register<T0>(arg0: Registry, arg1: super::pool::Pool<T0>) = PACKAGE_ID::registry::register;
+    }
+}
@@ -1,228 +1,121 @@
-use anyhow::{anyhow, bail, ensure};
-use common::fetch_sorted_gas_coins;
-use serde::{Deserialize, Serialize};
+use anyhow::ensure;
+use common::{
+    disassemble, estimate_gas_budget_with_policy, fetch_sorted_gas_coins, find_min_gas_budget,
+    framework::{coin, kiosk, math, Coin, Kiosk, KioskOwnerCap, SUI},
+    network_fixture_path, select_gas_coins, to_mermaid, Config, DevInspectOutcome, FixtureMode,
+    FixtureServer, GasBudgetPolicy, Labels, NodeApi, Res, SelectionStrategy, TypedPtb,
+};
 use std::str::FromStr;
 use sui_sdk::{rpc_types::SuiTransactionBlockEffectsAPI, SuiClientBuilder};
 use sui_types::{
-    balance::Balance,
-    base_types::SuiAddress,
-    coin::{self, Coin},
-    id::{ID, UID},
-    programmable_transaction_builder::ProgrammableTransactionBuilder,
-    transaction::{Argument, ObjectArg, TransactionData, TransactionKind},
-    Identifier, TypeTag, SUI_FRAMEWORK_PACKAGE_ID,
+    base_types::{ObjectRef, SuiAddress},
+    id::ID,
+    transaction::{ObjectArg, ProgrammableTransaction, TransactionData, TransactionKind},
 };
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
+    // Pick random address owning at least 2 non-empty SUI coin objects
+    let config = Config::load(SuiAddress::from_str(
+        "0x43b8f743162704af85214b0d0159fbef11aae0e996a8e9eac7fafda7fc5bd5f2",
+    )?)?;
+
+    // Set `SUI_RPC_FIXTURE_MODE` to `record` or `replay` to capture or replay the RPC traffic
+    let fixture_mode = FixtureMode::from_env(network_fixture_path(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"),
+        &config.network,
+    ))?;
+    let fixture_server = FixtureServer::start(fixture_mode, config.rpc_url()).await?;
+
+    // Save the recording even when the run fails, as it is the most useful to debug then
+    let result = async {
+        let rpc_client = SuiClientBuilder::default()
+            .build(fixture_server.url())
+            .await?;
+        config.verify_chain_identifier(&rpc_client).await?;
+        run(&rpc_client, &config).await
+    }
+    .await;
+    let finished = fixture_server.finish().await;
+    result?;
+    finished
+}
+
+async fn run(rpc_client: &impl NodeApi, config: &Config) -> anyhow::Result<()> {
     // ---------------------------------------------------------------------------------------------
     // Setup
 
-    let mut pt_builder = ProgrammableTransactionBuilder::new();
-
-    // Pick random address owning at least 2 non-empty SUI coin objects
-    let sender =
-        SuiAddress::from_str("0x43b8f743162704af85214b0d0159fbef11aae0e996a8e9eac7fafda7fc5bd5f2")?;
-
-    let rpc_client = SuiClientBuilder::default()
-        .build("https://fullnode.mainnet.sui.io:443")
-        .await?;
+    let sender = config.sender;
 
-    let gas_price = rpc_client.read_api().get_reference_gas_price().await?;
-    let gas_coins = fetch_sorted_gas_coins(&rpc_client, &sender).await?;
+    let gas_price = rpc_client.get_reference_gas_price().await?;
+    let gas_coins = fetch_sorted_gas_coins(rpc_client, &sender).await?;
 
     ensure!(gas_coins.len() > 1, "Need at least 2 non-empty gas coins"); // 1 for gas, 1 for the coin we're manipulating
 
     let (richest_coin, _) = gas_coins.first().unwrap();
-    let original_coin_arg = ObjectArg::ImmOrOwnedObject(richest_coin.object_ref());
-    let original_coin_arg = pt_builder.obj(original_coin_arg)?;
 
-    let number_two_arg = pt_builder.pure(2u64)?;
+    // provisional budget, the actual one is estimated from a dry run once the PTB is built
+    let gas_budget = config.gas_budget;
 
-    let gas_payment = gas_coins[1..]
-        .iter()
-        .map(|(coin, _)| coin.object_ref())
-        .collect::<Vec<_>>();
+    // pay for gas with any coins but the one manipulated in the PTB
+    let gas_payment = select_gas_coins(
+        &gas_coins,
+        gas_budget,
+        &[richest_coin.object_id],
+        SelectionStrategy::LargestFirst,
+    )?
+    .iter()
+    .map(|(coin, _)| coin.object_ref())
+    .collect::<Vec<_>>();
 
     // ---------------------------------------------------------------------------------------------
     // Programmable Transaction (PT) building
 
-    // Create some re-usable vars
-    let math_module = Identifier::from_str("math")?;
-    let kiosk_module = Identifier::from_str("kiosk")?;
-    let sui_coin_arg_type = TypeTag::from_str("0x2::sui::SUI")?;
-    let value_function = Identifier::from_str("value")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L86-L89
-    let destroy_zero_function = Identifier::from_str("destroy_zero")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L223-L228
-
-    // 0: get the balance of the provided coin
-    let initial_value_result = pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        coin::COIN_MODULE_NAME.to_owned(),
-        value_function.to_owned(),
-        vec![sui_coin_arg_type.to_owned()],
-        vec![original_coin_arg],
-    );
-
-    // 1: calculate half the balance
-    let divide_function = Identifier::from_str("divide_and_round_up")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/math.move#L136-L143
-    let target_balance_result = pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        math_module.to_owned(),
-        divide_function,
-        vec![],
-        vec![initial_value_result, number_two_arg],
-    );
-
-    // 2: split the original coin into a new one with balance equal to the target balance
-    let split_function = Identifier::from_str("split")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L164-L170
-    let new_coin_result = pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        coin::COIN_MODULE_NAME.to_owned(),
-        split_function,
-        vec![sui_coin_arg_type.clone()],
-        vec![original_coin_arg, target_balance_result],
-    );
-
-    // 3: get the balance of the new coin
-    let new_coin_value_result = pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        coin::COIN_MODULE_NAME.to_owned(),
-        value_function.to_owned(),
-        vec![sui_coin_arg_type.to_owned()],
-        vec![new_coin_result],
-    );
-
-    // 4: create an empty SUI coin
-    let zero_function = Identifier::from_str("zero")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L217-L221
-    let empty_coin_result = pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        coin::COIN_MODULE_NAME.to_owned(),
-        zero_function,
-        vec![sui_coin_arg_type.to_owned()],
-        vec![],
-    );
-
-    // 5: destroy the empty SUI coin
-    pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        coin::COIN_MODULE_NAME.to_owned(),
-        destroy_zero_function.to_owned().to_owned(),
-        vec![sui_coin_arg_type.to_owned()],
-        vec![empty_coin_result],
-    ); // ignore the result, this function returns nothing
-
-    // 6: create a new kiosk
-    let new_function = Identifier::from_str("new")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/kiosk/kiosk.move#L184C1-L200
-    let new_kiosk_result = pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        kiosk_module.to_owned(),
-        new_function,
-        vec![],
-        vec![],
-    );
-
-    // Extract the internal index of this transaction so that we can use it to refer to the
-    // nested results returned by this function (tuple).
-    let Argument::Result(kiosk_result) = new_kiosk_result else { bail!("This outta be a Result") };
-
-    // 7: check if kiosk contains a specific item (here the original coin, which it does not)
-    let first_item = 0;
-    let kiosk_arg = Argument::NestedResult(kiosk_result.to_owned(), first_item); // Point to the first item in the nested results of the kiosk creation.
-    let non_existent_id = ID::new(richest_coin.object_id);
-    let non_existent_id_arg = pt_builder.pure(non_existent_id)?;
-    let has_item_function = Identifier::from_str("has_item")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/kiosk/kiosk.move#L414-L417
-    pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        kiosk_module.to_owned(),
-        has_item_function,
-        vec![],
-        vec![kiosk_arg.to_owned(), non_existent_id_arg],
-    ); // ignore the result, we will not use it in this PTB
-
-    // 8: close the kiosk and retrieve the coin for the balance it contained
-    let second_item = 1;
-    let kiosk_owner_cap_arg = Argument::NestedResult(kiosk_result.to_owned(), second_item); // Point to the second item in the nested results of the kiosk creation.
-    let close_and_withdraw_function = Identifier::from_str("close_and_withdraw")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/kiosk/kiosk.move#L202-L218
-    let remaining_kiosk_coin_result = pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        kiosk_module.to_owned(),
-        close_and_withdraw_function,
-        vec![],
-        vec![kiosk_arg, kiosk_owner_cap_arg],
-    );
-
-    // 9: destroy the empty kiosk withdrawal coin
-    pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        coin::COIN_MODULE_NAME.to_owned(),
-        destroy_zero_function.to_owned(),
-        vec![sui_coin_arg_type.to_owned()],
-        vec![remaining_kiosk_coin_result],
-    ); // ignore the result, this function returns nothing
-
-    // 10: calculate the absolute difference between the initial value (retrieved in transaction 0)
-    // and the new coin value (retrieved in transaction 3)
-    let diff_function = Identifier::from_str("diff")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/math.move#L25-L32
-    pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        math_module.to_owned(),
-        diff_function,
-        vec![],
-        vec![new_coin_value_result, initial_value_result],
-    ); // ignore the result, we will not use it in this PTB
-
-    // 11: merge new coin into original coin
-    let join_function = Identifier::from_str("join")?; // https://github.com/MystenLabs/sui/blob/main/crates/sui-framework/packages/sui-framework/sources/coin.move#L148-L154
-    pt_builder.programmable_move_call(
-        SUI_FRAMEWORK_PACKAGE_ID,
-        coin::COIN_MODULE_NAME.to_owned(),
-        join_function,
-        vec![sui_coin_arg_type.clone()],
-        vec![original_coin_arg.to_owned(), new_coin_result],
-    ); // ignore the result, this function returns nothing
-
-    // 12: transfer back the original coin to the sender to avoid tx failure due to non-droppable object still existing
-    pt_builder.transfer_arg(sender, original_coin_arg);
-
-    let pt = pt_builder.finish();
+    let (
+        pt,
+        labels,
+        Results {
+            initial_value_result,
+            target_balance_result,
+            new_coin_result,
+            new_coin_value_result,
+            empty_coin_result,
+            kiosk_arg,
+            kiosk_owner_cap_arg,
+            kiosk_has_item_result,
+            diff_result,
+        },
+    ) = build_pt(sender, richest_coin.object_ref())?;
+    println!("{}", to_mermaid(&pt, &labels));
+
+    println!("{}", disassemble(&pt, &labels)); // For the curious
 
     // ---------------------------------------------------------------------------------------------
     // Execution and inspection of results
 
-    // println!("{:#?}", pt.clone()); // For the curious
-
     let tx_data = TransactionKind::ProgrammableTransaction(pt.to_owned());
 
     let response = rpc_client
-        .read_api()
-        .dev_inspect_transaction_block(sender, tx_data, None, None)
+        .dev_inspect_transaction_block(sender, tx_data)
         .await?;
 
-    if let Some(e) = response.error {
-        println!("Transaction failed: {}", e);
-        return Ok(());
-    }
-
-    let execution_results = response.results.ok_or(anyhow!("There should be results"))?;
+    let outcome = DevInspectOutcome::new(response)?;
 
     ensure!(
-        execution_results.len() == 13,
+        outcome.command_count() == 13,
         "There should be 13 results, one for each transaction in the block, found {}",
-        execution_results.len()
+        outcome.command_count()
     );
 
-    let (original_coin_value_bytes, _) = execution_results[0].clone().return_values[0].clone();
-    let original_coin_value: u64 = bcs::from_bytes(&original_coin_value_bytes)?;
+    let original_coin_value = outcome.get(initial_value_result)?;
     println!("--> tx 0");
     println!("original_coin_value: {}", original_coin_value);
 
-    let (new_coin_value_target_bytes, _) = execution_results[1].clone().return_values[0].clone();
-    let new_coin_value_target: u64 = bcs::from_bytes(&new_coin_value_target_bytes)?;
+    let new_coin_value_target = outcome.get(target_balance_result)?;
     println!("--> tx 1");
     println!("new_coin_value_target: {}", new_coin_value_target);
 
-    let (new_coin_bytes, _) = execution_results[2].clone().return_values[0].clone();
-    let new_coin: Coin = bcs::from_bytes(&new_coin_bytes)?;
+    let new_coin = outcome.get(new_coin_result)?;
     println!("--> tx 2");
     println!("new_coin: {:?}", new_coin);
     ensure!(
@@ -230,13 +123,11 @@ async fn main() -> anyhow::Result<()> {
         "New coin value should be equal to the target value"
     );
 
-    let (new_coin_value_bytes, _) = execution_results[3].clone().return_values[0].clone();
-    let new_coin_value: u64 = bcs::from_bytes(&new_coin_value_bytes)?;
+    let new_coin_value = outcome.get(new_coin_value_result)?;
     println!("--> tx 3");
     println!("new_coin_value: {}", new_coin_value);
 
-    let (empty_coin_bytes, _) = execution_results[4].clone().return_values[0].clone();
-    let zero_coin: Coin = bcs::from_bytes(&empty_coin_bytes)?;
+    let zero_coin = outcome.get(empty_coin_result)?;
     println!("--> tx 4");
     println!("zero_coin: {:?}", zero_coin);
     ensure!(zero_coin.value() == 0, "Empty coin value should be 0");
@@ -244,16 +135,13 @@ async fn main() -> anyhow::Result<()> {
     println!("--> tx 5");
 
     println!("--> tx 6");
-    let (kiosk_bytes, _) = execution_results[6].clone().return_values[0].clone();
-    let kiosk: Kiosk = bcs::from_bytes(&kiosk_bytes)?;
+    let kiosk = outcome.get(kiosk_arg)?;
     println!("kiosk: {:?}", kiosk);
-    let (kiosk_owner_cap_bytes, _) = execution_results[6].clone().return_values[1].clone();
-    let kiosk_owner_cap: KioskOwnerCap = bcs::from_bytes(&kiosk_owner_cap_bytes)?;
+    let kiosk_owner_cap = outcome.get(kiosk_owner_cap_arg)?;
     println!("kiosk_owner_cap: {:?}", kiosk_owner_cap);
 
     println!("--> tx 7");
-    let (kiosk_has_id_bytes, _) = execution_results[7].clone().return_values[0].clone();
-    let kiosk_has_id: bool = bcs::from_bytes(&kiosk_has_id_bytes)?;
+    let kiosk_has_id = outcome.get(kiosk_has_item_result)?;
     println!("kiosk_has_id: {:?}", kiosk_has_id);
 
     println!("--> tx 8");
@@ -261,8 +149,7 @@ async fn main() -> anyhow::Result<()> {
     println!("--> tx 9");
 
     println!("--> tx 10");
-    let (diff_bytes, _) = execution_results[10].clone().return_values[0].clone();
-    let diff: u64 = bcs::from_bytes(&diff_bytes)?;
+    let diff = outcome.get(diff_result)?;
     println!("diff: {}", diff);
     ensure!(
         diff == original_coin_value.abs_diff(new_coin_value),
@@ -276,59 +163,354 @@ async fn main() -> anyhow::Result<()> {
     // ---------------------------------------------------------------------------------------------
     // Verify dry run succeeds
 
+    let estimate = estimate_gas_budget_with_policy(
+        rpc_client,
+        sender,
+        pt.clone(),
+        gas_payment.clone(),
+        &GasBudgetPolicy {
+            provisional_budget: gas_budget,
+            ..Default::default()
+        },
+    )
+    .await?;
+    println!(
+        "Estimated gas budget: {} (net cost {})",
+        estimate.budget,
+        estimate.net_cost()
+    );
+
+    // how far the margin could be cut, to the gas price. Opt-in with `--find-min-budget`, as the
+    // search takes ~20 more dry runs.
+    if config.find_min_budget {
+        let min_budget = find_min_gas_budget(
+            rpc_client,
+            sender,
+            pt.clone(),
+            gas_payment.clone(),
+            estimate.budget,
+            gas_price,
+        )
+        .await?;
+        println!("{}", min_budget);
+    }
+
     let tx_data = TransactionData::new_programmable(
         sender,
         gas_payment,
         pt,
-        100_000_000,
+        estimate.budget,
         gas_price.to_owned(),
     );
 
-    let response = rpc_client
-        .read_api()
-        .dry_run_transaction_block(tx_data)
-        .await?;
+    let response = rpc_client.dry_run_transaction_block(tx_data).await?;
 
     println!("Dry run status: {:#?}", response.effects.status());
 
     Ok(())
 }
 
-// Original move structs
-
-// struct Kiosk has key, store {
-//     id: UID,
-//     /// Balance of the Kiosk - all profits from sales go here.
-//     profits: Balance<SUI>,
-//     /// Always point to `sender` of the transaction.
-//     /// Can be changed by calling `set_owner` with Cap.
-//     owner: address,
-//     /// Number of items stored in a Kiosk. Used to allow unpacking
-//     /// an empty Kiosk if it was wrapped or has a single owner.
-//     item_count: u32,
-//     /// Whether to open the UID to public. Set to `true` by default
-//     /// but the owner can switch the state if necessary.
-//     allow_extensions: bool
-// }
-//
-// struct KioskOwnerCap has key, store {
-//     id: UID,
-//     for: ID
-// }
-
-// Mirrored structs for deserialization
-
-#[derive(Debug, Serialize, Deserialize)]
-struct Kiosk {
-    id: UID,
-    profits: Balance,
-    owner: SuiAddress,
-    item_count: u32,
-    allow_extensions: bool,
+/// Handles on the PTB results read back from dev-inspect
+struct Results {
+    initial_value_result: Res<u64>,
+    target_balance_result: Res<u64>,
+    new_coin_result: Res<Coin<SUI>>,
+    new_coin_value_result: Res<u64>,
+    empty_coin_result: Res<Coin<SUI>>,
+    kiosk_arg: Res<Kiosk>,
+    kiosk_owner_cap_arg: Res<KioskOwnerCap>,
+    kiosk_has_item_result: Res<bool>,
+    diff_result: Res<u64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct KioskOwnerCap {
-    id: UID,
-    for_: ID,
+/// Build the PTB manipulating `original_coin`, along with the names of its arguments
+fn build_pt(
+    sender: SuiAddress,
+    original_coin: ObjectRef,
+) -> anyhow::Result<(ProgrammableTransaction, Labels, Results)> {
+    let mut pt_builder = TypedPtb::new();
+
+    let original_coin_arg = ObjectArg::ImmOrOwnedObject(original_coin);
+    let original_coin_arg: Res<Coin<SUI>> = pt_builder.obj(original_coin_arg)?;
+
+    let number_two_arg = pt_builder.pure(2u64)?;
+
+    // 0: get the balance of the provided coin
+    let initial_value_result = pt_builder.call(coin::value()?, (original_coin_arg,));
+
+    // 1: calculate half the balance
+    let target_balance_result = pt_builder.call(
+        math::divide_and_round_up()?,
+        (initial_value_result, number_two_arg),
+    );
+
+    // 2: split the original coin into a new one with balance equal to the target balance
+    let new_coin_result =
+        pt_builder.call(coin::split()?, (original_coin_arg, target_balance_result));
+
+    // 3: get the balance of the new coin
+    let new_coin_value_result = pt_builder.call(coin::value()?, (new_coin_result,));
+
+    // 4: create an empty SUI coin
+    let empty_coin_result = pt_builder.call(coin::zero::<SUI>()?, ());
+
+    // 5: destroy the empty SUI coin
+    pt_builder.call(coin::destroy_zero()?, (empty_coin_result,)); // this function returns nothing
+
+    // 6: create a new kiosk, each handle pointing to an item of the nested results returned by
+    // this function (tuple)
+    let (kiosk_arg, kiosk_owner_cap_arg) = pt_builder.call(kiosk::new()?, ());
+
+    // 7: check if kiosk contains a specific item (here the original coin, which it does not)
+    let non_existent_id = ID::new(original_coin.0);
+    let non_existent_id_arg = pt_builder.pure(non_existent_id)?;
+    let kiosk_has_item_result =
+        pt_builder.call(kiosk::has_item()?, (kiosk_arg, non_existent_id_arg));
+
+    // 8: close the kiosk and retrieve the coin for the balance it contained
+    let remaining_kiosk_coin_result = pt_builder.call(
+        kiosk::close_and_withdraw()?,
+        (kiosk_arg, kiosk_owner_cap_arg),
+    );
+
+    // 9: destroy the empty kiosk withdrawal coin
+    pt_builder.call(coin::destroy_zero()?, (remaining_kiosk_coin_result,)); // this function returns nothing
+
+    // 10: calculate the absolute difference between the initial value (retrieved in transaction 0)
+    // and the new coin value (retrieved in transaction 3)
+    let diff_result = pt_builder.call(math::diff()?, (new_coin_value_result, initial_value_result));
+
+    // 11: merge new coin into original coin
+    pt_builder.call(coin::join()?, (original_coin_arg, new_coin_result)); // this function returns nothing
+
+    // 12: transfer back the original coin to the sender to avoid tx failure due to non-droppable object still existing
+    let recipient_arg = pt_builder.transfer(sender, original_coin_arg);
+
+    let pt = pt_builder.finish();
+
+    // Names for the diagram of the PTB, as drawn in the README, and its disassembly
+    let mut labels = Labels::new();
+    labels
+        .name(original_coin_arg, "original coin")
+        .name(number_two_arg, "2")
+        .name(initial_value_result, "original coin value")
+        .name(target_balance_result, "original coin value / 2")
+        .name(new_coin_result, "new coin")
+        .name(new_coin_value_result, "new coin value")
+        .name(empty_coin_result, "empty coin")
+        .name(kiosk_arg, "kiosk")
+        .name(kiosk_owner_cap_arg, "kiosk owner cap")
+        .name(non_existent_id_arg, "original coin id")
+        .name(remaining_kiosk_coin_result, "remainder coin")
+        .name(recipient_arg, "sender");
+
+    Ok((
+        pt,
+        labels,
+        Results {
+            initial_value_result,
+            target_balance_result,
+            new_coin_result,
+            new_coin_value_result,
+            empty_coin_result,
+            kiosk_arg,
+            kiosk_owner_cap_arg,
+            kiosk_has_item_result,
+            diff_result,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use common::{
+        config::DEFAULT_GAS_BUDGET,
+        fake::{dev_inspect_failure, dev_inspect_results, sui_coin_object},
+        FakeNode, MoveType, Network,
+    };
+    use sui_types::{
+        base_types::{ObjectDigest, ObjectID, SequenceNumber},
+        gas::GasCostSummary,
+        transaction::TransactionDataAPI,
+        TypeTag,
+    };
+
+    use super::*;
+
+    const ORIGINAL_COIN_VALUE: u64 = 1_000_000_001;
+    const NEW_COIN_VALUE: u64 = 500_000_001;
+
+    fn config() -> Config {
+        Config {
+            network: Network::Localnet,
+            sender: SuiAddress::ZERO,
+            gas_budget: DEFAULT_GAS_BUDGET,
+            keystore: PathBuf::new(),
+            chain_identifier: None,
+            find_min_budget: false,
+        }
+    }
+
+    fn u64_value(n: u64) -> (Vec<u8>, TypeTag) {
+        (bcs::to_bytes(&n).unwrap(), TypeTag::U64)
+    }
+
+    /// `Coin<SUI>` as laid out in BCS: its UID, then its balance
+    fn coin_value(id: u8, balance: u64) -> (Vec<u8>, TypeTag) {
+        let coin = (ObjectID::new([id; 32]), balance);
+        (bcs::to_bytes(&coin).unwrap(), Coin::<SUI>::type_tag())
+    }
+
+    /// What the node returns for each of the 13 commands of the example
+    fn return_values() -> Vec<Vec<(Vec<u8>, TypeTag)>> {
+        let kiosk_id = ObjectID::new([0xb; 32]);
+        let kiosk = (kiosk_id, 0u64, SuiAddress::ZERO, 0u32, false);
+        let kiosk_owner_cap = (ObjectID::new([0xc; 32]), kiosk_id);
+        vec![
+            vec![u64_value(ORIGINAL_COIN_VALUE)],
+            vec![u64_value(NEW_COIN_VALUE)],
+            vec![coin_value(0xa, NEW_COIN_VALUE)],
+            vec![u64_value(NEW_COIN_VALUE)],
+            vec![coin_value(0xd, 0)],
+            vec![],
+            vec![
+                (bcs::to_bytes(&kiosk).unwrap(), Kiosk::type_tag()),
+                (
+                    bcs::to_bytes(&kiosk_owner_cap).unwrap(),
+                    KioskOwnerCap::type_tag(),
+                ),
+            ],
+            vec![(bcs::to_bytes(&false).unwrap(), TypeTag::Bool)],
+            vec![coin_value(0xe, 0)],
+            vec![],
+            vec![u64_value(ORIGINAL_COIN_VALUE - NEW_COIN_VALUE)],
+            vec![],
+            vec![],
+        ]
+    }
+
+    fn node() -> FakeNode {
+        let gas_cost_summary = GasCostSummary {
+            computation_cost: 1_000_000,
+            storage_cost: 5_000_000,
+            storage_rebate: 4_000_000,
+            non_refundable_storage_fee: 0,
+        };
+        FakeNode::new()
+            .with_owned_objects(
+                SuiAddress::ZERO,
+                vec![
+                    sui_coin_object(ObjectID::new([1; 32]), ORIGINAL_COIN_VALUE),
+                    sui_coin_object(ObjectID::new([2; 32]), 2 * DEFAULT_GAS_BUDGET),
+                ],
+            )
+            .with_min_gas_budget(6_000_000, gas_cost_summary)
+    }
+
+    #[tokio::test]
+    async fn example_runs_against_the_fake_node() {
+        let node = node().push_dev_inspect(Ok(dev_inspect_results(return_values())));
+
+        run(&node, &config()).await.unwrap();
+
+        // The estimate, then the dry run with the estimated budget
+        let budgets: Vec<_> = node.dry_runs().iter().map(|tx| tx.gas_budget()).collect();
+        assert_eq!(budgets, vec![DEFAULT_GAS_BUDGET, 7_200_000]);
+        // The manipulated coin never pays for gas
+        let gas_payment = node.dry_runs()[0].gas().to_vec();
+        assert_eq!(gas_payment.len(), 1);
+        assert_eq!(gas_payment[0].0, ObjectID::new([2; 32]));
+    }
+
+    #[tokio::test]
+    async fn example_searches_the_min_budget_on_demand() {
+        let node = node().push_dev_inspect(Ok(dev_inspect_results(return_values())));
+
+        let config = Config {
+            find_min_budget: true,
+            ..config()
+        };
+        run(&node, &config).await.unwrap();
+
+        assert!(node.dry_runs().len() > 2);
+    }
+
+    #[tokio::test]
+    async fn example_checks_the_split_coin_value() {
+        let mut return_values = return_values();
+        return_values[2] = vec![coin_value(0xa, NEW_COIN_VALUE - 1)];
+        let node = node().push_dev_inspect(Ok(dev_inspect_results(return_values)));
+
+        assert!(run(&node, &config()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn example_fails_when_dev_inspect_aborts() {
+        let node = node().push_dev_inspect(Ok(dev_inspect_failure(
+            "MoveAbort(MoveLocation { .. }, 0) in command 2",
+        )));
+
+        assert!(run(&node, &config()).await.is_err());
+        assert!(node.dry_runs().is_empty());
+    }
+
+    /// Disassembly of the example PTB. Run with `UPDATE_GOLDEN=1` to rewrite it after changing the
+    /// PTB or the disassembler.
+    const DISASSEMBLY: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/disassembly.txt");
+
+    #[test]
+    fn disassembly_matches_the_snapshot() {
+        let original_coin = (
+            ObjectID::new([1; 32]),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::new([0; 32]),
+        );
+        let (pt, labels, _) = build_pt(SuiAddress::ZERO, original_coin).unwrap();
+
+        let disassembly = disassemble(&pt, &labels);
+        if std::env::var_os("UPDATE_GOLDEN").is_some() {
+            std::fs::write(DISASSEMBLY, &disassembly).unwrap();
+        }
+        let expected = std::fs::read_to_string(DISASSEMBLY).unwrap();
+        assert_eq!(
+            disassembly, expected,
+            "fixtures/disassembly.txt is outdated, run with UPDATE_GOLDEN=1 to rewrite it"
+        );
+    }
+
+    /// Session of the example against the fake node, recorded through the SDK. Run with
+    /// `UPDATE_FIXTURES=1` to record it again after changing the example.
+    const RECORDED_SESSION: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/fake-node.json");
+
+    #[tokio::test]
+    async fn example_replays_its_recorded_session() {
+        let path = PathBuf::from(RECORDED_SESSION);
+        if std::env::var_os("UPDATE_FIXTURES").is_some() || !path.exists() {
+            let node = node().push_dev_inspect(Ok(dev_inspect_results(return_values())));
+            let recorder =
+                FixtureServer::start_with_node(FixtureMode::Record(path.clone()), Arc::new(node))
+                    .await
+                    .unwrap();
+            let rpc_client = SuiClientBuilder::default()
+                .build(recorder.url())
+                .await
+                .unwrap();
+            let result = run(&rpc_client, &config()).await;
+            recorder.finish().await.unwrap();
+            result.unwrap();
+        }
+
+        let replay = FixtureServer::start(FixtureMode::Replay(path), "")
+            .await
+            .unwrap();
+        let rpc_client = SuiClientBuilder::default()
+            .build(replay.url())
+            .await
+            .unwrap();
+        let result = run(&rpc_client, &config()).await;
+        replay.finish().await.unwrap();
+        result.unwrap();
+    }
 }
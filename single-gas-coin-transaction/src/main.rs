@@ -1,5 +1,8 @@
-use anyhow::{bail, ensure};
-use common::fetch_sorted_gas_coins;
+use anyhow::{anyhow, bail, ensure};
+use common::{
+    estimate_gas_budget_until_stable, fetch_sorted_gas_coins, lint, network_fixture_path,
+    rewrite_gas_coin, Config, FixtureMode, FixtureServer, GasBudgetPolicy, GasCoinUse, NodeApi,
+};
 use std::str::FromStr;
 use sui_sdk::{
     rpc_types::{SuiExecutionStatus::Success, SuiTransactionBlockEffectsAPI},
@@ -9,23 +12,44 @@ use sui_types::{
     base_types::SuiAddress,
     coin,
     programmable_transaction_builder::ProgrammableTransactionBuilder,
-    transaction::{Argument, Command, ObjectArg, TransactionData},
+    transaction::{Argument, ObjectArg, TransactionData},
     Identifier, TypeTag, SUI_FRAMEWORK_PACKAGE_ID,
 };
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    let sender =
-        SuiAddress::from_str("0xe719405821d7bd32ded86a2aed34f06f3dacd09c91241ec3f34b219ebeddc6f0")?;
+    let config = Config::load(SuiAddress::from_str(
+        "0xe719405821d7bd32ded86a2aed34f06f3dacd09c91241ec3f34b219ebeddc6f0",
+    )?)?;
+
+    // Set `SUI_RPC_FIXTURE_MODE` to `record` or `replay` to capture or replay the RPC traffic
+    let fixture_mode = FixtureMode::from_env(network_fixture_path(
+        concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures"),
+        &config.network,
+    ))?;
+    let fixture_server = FixtureServer::start(fixture_mode, config.rpc_url()).await?;
+
+    // Save the recording even when the run fails, as it is the most useful to debug then
+    let result = async {
+        let rpc_client = SuiClientBuilder::default()
+            .build(fixture_server.url())
+            .await?;
+        config.verify_chain_identifier(&rpc_client).await?;
+        run(&rpc_client, &config).await
+    }
+    .await;
+    let finished = fixture_server.finish().await;
+    result?;
+    finished
+}
 
-    let rpc_client = SuiClientBuilder::default()
-        .build("https://fullnode.mainnet.sui.io:443")
-        .await?;
+async fn run(rpc_client: &impl NodeApi, config: &Config) -> anyhow::Result<()> {
+    let sender = config.sender;
 
-    let gas_budget = 100_000_000; // 0.1 Sui
-    let gas_price = rpc_client.read_api().get_reference_gas_price().await?;
+    let gas_budget = config.gas_budget;
+    let gas_price = rpc_client.get_reference_gas_price().await?;
 
-    let gas_coins = fetch_sorted_gas_coins(&rpc_client, &sender).await?;
+    let gas_coins = fetch_sorted_gas_coins(rpc_client, &sender).await?;
     ensure!(gas_coins.len() > 0, "Need at least 1 non-empty gas coin");
 
     let (primary_coin, primary_coin_balance) = gas_coins.first().unwrap();
@@ -52,19 +76,21 @@ async fn main() -> anyhow::Result<()> {
     println!("Bad PTB inputs: {:?}", bad_pt.inputs);
     let bad_tx_data = TransactionData::new_programmable(
         sender,
-        gas_payment,
-        bad_pt,
+        gas_payment.clone(),
+        bad_pt.clone(),
         gas_budget.to_owned(),
         gas_price.to_owned(),
     );
 
+    // The mistake can be caught before any round-trip to the node
+    for finding in lint(&bad_tx_data) {
+        println!("Lint: {}", finding);
+    }
+
     // ---------------------------------------------------------------------------------------------
     // Simulate naive PTB
 
-    let result = rpc_client
-        .read_api()
-        .dry_run_transaction_block(bad_tx_data)
-        .await;
+    let result = rpc_client.dry_run_transaction_block(bad_tx_data).await;
 
     match result {
         Ok(_) => bail!("Huh"),
@@ -74,41 +100,59 @@ async fn main() -> anyhow::Result<()> {
     // ---------------------------------------------------------------------------------------------
     // Build correct PTB
 
-    let mut pt_builder = ProgrammableTransactionBuilder::new();
-
-    // provide primary coin as unique gas payment object
-    let gas_payment = vec![primary_coin.object_ref()];
-
-    // split the primary into a new usable coin for the PTB logic
-    let usable_balance = pt_builder.pure(primary_coin_balance - gas_budget)?;
+    // Rewrite the naive PTB: the primary coin input becomes a usable coin split off
+    // `Argument::GasCoin`, holding everything but the gas budget.
     // N.B.: the `Argument::GasCoin` points to the coin used for gas payment, which coincides with
-    // `primary_coin_arg` here. However it would be incorrect to specify `primary_coin_arg` explicitly,
-    // and would result in the same duplicate issue
-    let Argument::Result(split_coin_result) = pt_builder.command(Command::SplitCoins(Argument::GasCoin, vec![usable_balance])) else { bail!("This outta be a Result") };
-    let usable_coin = Argument::NestedResult(split_coin_result, 0);
+    // `primary_coin_arg` here. However it is incorrect to specify `primary_coin_arg` explicitly, as
+    // the naive PTB does
+    let build_good_pt = |gas_budget: u64| {
+        let amount = primary_coin_balance
+            .checked_sub(gas_budget)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Gas budget {} exceeds the primary coin balance {}",
+                    gas_budget,
+                    primary_coin_balance
+                )
+            })?;
+        rewrite_gas_coin(&bad_pt, &gas_payment, GasCoinUse::Split { amount })
+    };
 
-    // do stuff
-    build_pt_logic(&mut pt_builder, &sender, usable_coin)?;
+    // The split amount depends on the budget, which depends on the PTB: estimate both together,
+    // starting from the configured budget
+    let estimate = estimate_gas_budget_until_stable(
+        rpc_client,
+        sender,
+        gas_payment.clone(),
+        &GasBudgetPolicy {
+            provisional_budget: gas_budget,
+            ..Default::default()
+        },
+        build_good_pt,
+    )
+    .await?;
+    println!("Estimated gas budget: {}", estimate.budget);
+    let good_pt = build_good_pt(estimate.budget)?;
 
-    // finalize PTB and specify gas parameters
-    let good_pt = pt_builder.finish();
     println!("-------------------------------------------");
     println!("Good PTB inputs: {:?}", good_pt.inputs);
     let good_tx_data = TransactionData::new_programmable(
         sender,
         gas_payment,
         good_pt,
-        gas_budget.to_owned(),
+        estimate.budget,
         gas_price.to_owned(),
     );
 
+    ensure!(
+        lint(&good_tx_data).is_empty(),
+        "Good PTB should pass the lint"
+    );
+
     // ---------------------------------------------------------------------------------------------
     // Simulate correct PTB
 
-    let result = rpc_client
-        .read_api()
-        .dry_run_transaction_block(good_tx_data)
-        .await;
+    let result = rpc_client.dry_run_transaction_block(good_tx_data).await;
 
     match result {
         Ok(response) if response.effects.status() == &Success => {
@@ -150,3 +194,188 @@ fn build_pt_logic(
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use common::{
+        config::DEFAULT_GAS_BUDGET,
+        fake::{dry_run_response, sui_coin_object},
+        framework::{Coin, SUI},
+        to_mermaid, Error, FakeNode, Labels, Network, Res,
+    };
+    use sui_sdk::rpc_types::SuiExecutionStatus;
+    use sui_types::{
+        base_types::{ObjectDigest, ObjectID, SequenceNumber},
+        gas::GasCostSummary,
+        transaction::{CallArg, TransactionDataAPI, TransactionKind},
+    };
+
+    use super::*;
+
+    fn config() -> Config {
+        Config {
+            network: Network::Localnet,
+            sender: SuiAddress::ZERO,
+            gas_budget: DEFAULT_GAS_BUDGET,
+            keystore: PathBuf::new(),
+            chain_identifier: None,
+            find_min_budget: false,
+        }
+    }
+
+    /// Node rejecting transactions taking their gas coin as an input, like the real one does
+    fn node(primary_coin_balance: u64) -> FakeNode {
+        let primary_coin = ObjectID::new([1; 32]);
+        FakeNode::new()
+            .with_owned_objects(
+                SuiAddress::ZERO,
+                vec![
+                    sui_coin_object(primary_coin, primary_coin_balance),
+                    sui_coin_object(ObjectID::new([2; 32]), 1_000),
+                ],
+            )
+            .with_dry_run_handler(move |tx| {
+                let TransactionKind::ProgrammableTransaction(pt) = tx.kind() else {
+                    unreachable!("The example only builds PTBs")
+                };
+                let spends_gas_coin = pt.inputs.iter().any(|input| {
+                    matches!(input, CallArg::Object(ObjectArg::ImmOrOwnedObject((id, _, _))) if *id == primary_coin)
+                });
+                if spends_gas_coin {
+                    return Err(Error::Node(
+                        "Error checking transaction input objects: MutableObjectUsedMoreThanOnce"
+                            .to_string(),
+                    ));
+                }
+                let gas_cost_summary = GasCostSummary {
+                    computation_cost: 1_000_000,
+                    storage_cost: 2_000_000,
+                    storage_rebate: 1_500_000,
+                    non_refundable_storage_fee: 0,
+                };
+                Ok(dry_run_response(SuiExecutionStatus::Success, &gas_cost_summary))
+            })
+    }
+
+    #[tokio::test]
+    async fn example_runs_against_the_fake_node() {
+        let node = node(5_000_000_000);
+
+        run(&node, &config()).await.unwrap();
+
+        // The bad PTB, the budget estimate settling on its second round, then the good PTB
+        let budgets: Vec<_> = node.dry_runs().iter().map(|tx| tx.gas_budget()).collect();
+        assert_eq!(
+            budgets,
+            vec![DEFAULT_GAS_BUDGET, DEFAULT_GAS_BUDGET, 4_000_000, 4_000_000]
+        );
+    }
+
+    #[tokio::test]
+    async fn example_needs_a_coin_richer_than_the_budget() {
+        let node = node(DEFAULT_GAS_BUDGET);
+
+        assert!(run(&node, &config()).await.is_err());
+        assert!(node.dry_runs().is_empty());
+    }
+
+    #[test]
+    fn readme_diagrams_match_the_ptbs() {
+        let primary_coin = (
+            ObjectID::new([1; 32]),
+            SequenceNumber::from_u64(1),
+            ObjectDigest::new([0; 32]),
+        );
+        let mut pt_builder = ProgrammableTransactionBuilder::new();
+        let primary_coin_arg = pt_builder
+            .obj(ObjectArg::ImmOrOwnedObject(primary_coin))
+            .unwrap();
+        build_pt_logic(&mut pt_builder, &SuiAddress::ZERO, primary_coin_arg).unwrap();
+        let bad_pt = pt_builder.finish();
+        let good_pt = rewrite_gas_coin(
+            &bad_pt,
+            &[primary_coin],
+            GasCoinUse::Split { amount: 1_000 },
+        )
+        .unwrap();
+
+        let mut bad_labels = Labels::new();
+        bad_labels
+            .name(
+                Res::<Coin<SUI>>::unchecked(primary_coin_arg),
+                "primary coin",
+            )
+            .name(Res::<u64>::unchecked(Argument::Input(1)), "9")
+            .name(
+                Res::<u64>::unchecked(Argument::Result(0)),
+                "primary coin value",
+            )
+            .name(Res::<SuiAddress>::unchecked(Argument::Input(2)), "sender");
+        // The primary coin input is gone, the amount split off the gas coin being appended
+        let mut good_labels = Labels::new();
+        good_labels
+            .name(Res::<u64>::unchecked(Argument::Input(0)), "9")
+            .name(Res::<SuiAddress>::unchecked(Argument::Input(1)), "sender")
+            .name(Res::<u64>::unchecked(Argument::Input(2)), "usable amount")
+            .name(
+                Res::<Coin<SUI>>::unchecked(Argument::NestedResult(0, 0)),
+                "usable coin",
+            )
+            .name(
+                Res::<u64>::unchecked(Argument::Result(1)),
+                "usable coin value",
+            );
+
+        let readme =
+            std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/README.md")).unwrap();
+        let diagrams: Vec<&str> = readme
+            .split("```mermaid\n")
+            .skip(1)
+            .map(|block| block.split("```").next().unwrap())
+            .collect();
+        assert_eq!(
+            diagrams,
+            [
+                to_mermaid(&bad_pt, &bad_labels),
+                to_mermaid(&good_pt, &good_labels)
+            ],
+            "README.md diagrams are outdated"
+        );
+    }
+
+    /// Session of the example against the fake node, recorded through the SDK. Run with
+    /// `UPDATE_FIXTURES=1` to record it again after changing the example.
+    const RECORDED_SESSION: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/fake-node.json");
+
+    #[tokio::test]
+    async fn example_replays_its_recorded_session() {
+        let path = PathBuf::from(RECORDED_SESSION);
+        if std::env::var_os("UPDATE_FIXTURES").is_some() || !path.exists() {
+            let node = node(5_000_000_000);
+            let recorder =
+                FixtureServer::start_with_node(FixtureMode::Record(path.clone()), Arc::new(node))
+                    .await
+                    .unwrap();
+            let rpc_client = SuiClientBuilder::default()
+                .build(recorder.url())
+                .await
+                .unwrap();
+            let result = run(&rpc_client, &config()).await;
+            recorder.finish().await.unwrap();
+            result.unwrap();
+        }
+
+        let replay = FixtureServer::start(FixtureMode::Replay(path), "")
+            .await
+            .unwrap();
+        let rpc_client = SuiClientBuilder::default()
+            .build(replay.url())
+            .await
+            .unwrap();
+        let result = run(&rpc_client, &config()).await;
+        replay.finish().await.unwrap();
+        result.unwrap();
+    }
+}